@@ -1,10 +1,21 @@
 #![allow(dead_code)]
 
 use criterion::PlotConfiguration;
-use rand::distributions::{Distribution, Uniform};
-use rand::prelude::ThreadRng;
+use rand::distributions::Uniform;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::env;
 use vers_vecs::{BitVec, RsVec};
 
+/// Environment variable setting the seed of all generated inputs, shared with the benchmark harness.
+const SEED_VAR: &str = "VERS_BENCH_SEED";
+
+/// The seed used if none is configured, identical to the default of the benchmark harness.
+const DEFAULT_SEED: u64 = 0x7665_7273;
+
+/// Mixed into the seed of the query rng, so it is independent of the input rngs.
+const QUERY_STREAM: u64 = 0x9E37_79B9_7F4A_7C15;
+
 pub const SIZES: [usize; 11] = [
     1 << 8,
     1 << 10,
@@ -19,12 +30,42 @@ pub const SIZES: [usize; 11] = [
     1 << 28,
 ];
 
-pub fn construct_vers_vec(rng: &mut ThreadRng, len: usize) -> RsVec {
-    let sample = Uniform::new(0, u64::MAX);
+/// The global seed, read from the environment so runs of the criterion benchmarks are reproducible.
+pub fn seed() -> u64 {
+    env::var(SEED_VAR).map_or(DEFAULT_SEED, |seed| {
+        seed.parse()
+            .unwrap_or_else(|e| panic!("Invalid {SEED_VAR}: {e}"))
+    })
+}
+
+/// Create the rng generating the input of size `len`. It only depends on the seed and the size,
+/// so every library is benchmarked on the same input.
+pub fn input_rng(len: usize) -> StdRng {
+    StdRng::seed_from_u64(seed() ^ len as u64)
+}
+
+/// Create the rng generating query parameters.
+pub fn seeded_rng() -> StdRng {
+    StdRng::seed_from_u64(seed() ^ QUERY_STREAM)
+}
+
+/// Generate the words of a uniformly random bit vector with `len` bits.
+pub fn random_words(len: usize) -> Vec<u64> {
+    let sample = Uniform::new_inclusive(0, u64::MAX);
+    input_rng(len).sample_iter(sample).take(len / 64).collect()
+}
+
+/// Iterate over the bits of a bit vector stored in `words`, least significant bit first.
+pub fn bits(words: &[u64]) -> impl Iterator<Item = bool> + '_ {
+    words
+        .iter()
+        .flat_map(|&word| (0..64).map(move |i| (word >> i) & 1 == 1))
+}
 
+pub fn construct_vers_vec(words: &[u64]) -> RsVec {
     let mut bit_vec = BitVec::new();
-    for _ in 0..len / 64 {
-        bit_vec.append_word(sample.sample(rng));
+    for &word in words {
+        bit_vec.append_word(word);
     }
 
     RsVec::from_bit_vec(bit_vec)
@@ -3,7 +3,7 @@ use std::time::{Duration, Instant};
 use criterion::{BatchSize, BenchmarkId, black_box, Criterion, criterion_group, criterion_main};
 use cseq::elias_fano::Builder;
 use elias_fano::EliasFano;
-use rand::{Rng, thread_rng};
+use rand::Rng;
 use rand::distributions::{Distribution, Standard, Uniform};
 use sucds::mii_sequences::EliasFanoBuilder;
 use vers_vecs::EliasFanoVec;
@@ -14,10 +14,10 @@ fn elias_fano_random_access(b: &mut Criterion) {
     let mut group = b.benchmark_group("Elias-Fano: random-access");
     group.plot_config(common::plot_config());
 
-    let mut rng = thread_rng();
+    let mut rng = common::seeded_rng();
 
     for l in common::SIZES {
-        let mut sequence = thread_rng()
+        let mut sequence = common::input_rng(l)
             .sample_iter(Standard)
             .take(l)
             .collect::<Vec<u64>>();
@@ -93,7 +93,7 @@ fn elias_fano_in_order(b: &mut Criterion) {
     group.plot_config(common::plot_config());
 
     for l in common::SIZES {
-        let mut sequence = thread_rng()
+        let mut sequence = common::input_rng(l)
             .sample_iter(Standard)
             .take(l)
             .collect::<Vec<u64>>();
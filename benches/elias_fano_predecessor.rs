@@ -1,13 +1,13 @@
 use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
 use rand::distributions::{Distribution, Uniform};
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use sucds::mii_sequences::EliasFanoBuilder;
 use vers_vecs::EliasFanoVec;
 
 mod common;
 
 fn bench_ef_adversarial(b: &mut Criterion) {
-    let mut rng = thread_rng();
+    let mut rng = common::seeded_rng();
 
     let mut group = b.benchmark_group("Elias-Fano Predecessor: Adversarial Input");
     group.plot_config(common::plot_config());
@@ -70,7 +70,7 @@ fn bench_ef_adversarial(b: &mut Criterion) {
 }
 
 fn bench_ef_predecessor(b: &mut Criterion) {
-    let mut rng = thread_rng();
+    let mut rng = common::seeded_rng();
 
     let mut group = b.benchmark_group("Elias-Fano Predecessor: Random Input");
     group.plot_config(common::plot_config());
@@ -1,12 +1,10 @@
 use bio::data_structures::rank_select::RankSelect as BioRsVec;
-use bitm::{BitAccess, BitVec, CombinedSampling, Rank as BitmRank, RankSelect101111, Select0};
+use bitm::{CombinedSampling, Rank as BitmRank, RankSelect101111, Select0};
 use bv::BitVec as BioVec;
 use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
 use fid::{BitVector as FidVec, FID};
 use indexed_bitvec::IndexedBits;
-use rand::distributions::{Distribution, Standard, Uniform};
-use rand::rngs::ThreadRng;
-use rand::Rng;
+use rand::distributions::{Distribution, Uniform};
 use rsdict::RsDict;
 use succinct::select::Select0Support;
 use succinct::{BinSearchSelect, BitRankSupport, BitVecPush, BitVector as SuccinctVec, Rank9};
@@ -18,18 +16,18 @@ use sux::rank_small;
 
 mod common;
 
-fn construct_rsdict_vec(rng: &mut ThreadRng, len: usize) -> RsDict {
+fn construct_rsdict_vec(words: &[u64], len: usize) -> RsDict {
     let mut rs_dict = RsDict::with_capacity(len);
-    for _ in 0..len {
-        rs_dict.push(rng.gen_bool(0.5));
+    for bit in common::bits(words) {
+        rs_dict.push(bit);
     }
     rs_dict
 }
 
-fn construct_bio_vec(rng: &mut ThreadRng, len: usize) -> BioRsVec {
+fn construct_bio_vec(words: &[u64], len: usize) -> BioRsVec {
     let mut bio_vec = BioVec::new_fill(false, len as u64);
-    for i in 0..len {
-        bio_vec.set(i as u64, rng.gen_bool(0.5));
+    for (i, bit) in common::bits(words).enumerate() {
+        bio_vec.set(i as u64, bit);
     }
     // k chosen to be succinct after Cray's definition as outlined in the documentation
     BioRsVec::new(
@@ -38,80 +36,78 @@ fn construct_bio_vec(rng: &mut ThreadRng, len: usize) -> BioRsVec {
     )
 }
 
-fn construct_fair_bio_vec(rng: &mut ThreadRng, len: usize) -> BioRsVec {
+fn construct_fair_bio_vec(words: &[u64], len: usize) -> BioRsVec {
     let mut bio_vec = BioVec::new_fill(false, len as u64);
-    for i in 0..len {
-        bio_vec.set(i as u64, rng.gen_bool(0.5));
+    for (i, bit) in common::bits(words).enumerate() {
+        bio_vec.set(i as u64, bit);
     }
     // k chosen to be a fair comparison to vers
     BioRsVec::new(bio_vec, 512 / 32)
 }
 
-fn construct_fid_vec(rng: &mut ThreadRng, len: usize) -> FidVec {
+fn construct_fid_vec(words: &[u64]) -> FidVec {
     let mut fid_vec = FidVec::new();
-    for _ in 0..len {
-        fid_vec.push(rng.gen_bool(0.5));
+    for bit in common::bits(words) {
+        fid_vec.push(bit);
     }
     fid_vec
 }
 
-fn construct_ind_bit_vec(rng: &mut ThreadRng, len: usize) -> IndexedBits<Vec<u8>> {
-    let vec = rng.sample_iter(Standard).take(len / 8).collect::<Vec<u8>>();
+fn construct_ind_bit_vec(words: &[u64], len: usize) -> IndexedBits<Vec<u8>> {
+    // indexed_bitvec stores the most significant bit of each byte first
+    let vec = words
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .map(u8::reverse_bits)
+        .collect::<Vec<u8>>();
     IndexedBits::build_from_bytes(vec, len as u64).unwrap()
 }
 
-fn construct_rank9_vec(rng: &mut ThreadRng, len: usize) -> Rank9<SuccinctVec<u64>> {
+fn construct_rank9_vec(words: &[u64], len: usize) -> Rank9<SuccinctVec<u64>> {
     let mut bit_vec = SuccinctVec::with_capacity(len as u64);
-    for _ in 0..len {
-        bit_vec.push_bit(rng.sample(Standard))
+    for bit in common::bits(words) {
+        bit_vec.push_bit(bit)
     }
     Rank9::new(bit_vec)
 }
 
 fn construct_rank9_select_vec(
-    rng: &mut ThreadRng,
+    words: &[u64],
     len: usize,
 ) -> BinSearchSelect<Rank9<SuccinctVec<u64>>> {
-    BinSearchSelect::new(construct_rank9_vec(rng, len))
+    BinSearchSelect::new(construct_rank9_vec(words, len))
 }
 
-fn construct_sucds_vec(rng: &mut ThreadRng, len: usize) -> SucRank9Vec {
+fn construct_sucds_vec(words: &[u64], len: usize) -> SucRank9Vec {
     let mut suc_bv = SucBitVec::with_capacity(len);
-    for _ in 0..len / 64 {
+    for &word in words {
         suc_bv
-            .push_bits(rng.sample(Standard), 64)
+            .push_bits(word as usize, 64)
             .expect("Failed to push bits into sucds bitvector");
     }
 
     SucRank9Vec::new(suc_bv).select0_hints()
 }
 
-fn construct_sucds_darray(rng: &mut ThreadRng, len: usize) -> SucDArray {
+fn construct_sucds_darray(words: &[u64], len: usize) -> SucDArray {
     let mut suc_bv = SucBitVec::with_capacity(len);
-    for _ in 0..len / 64 {
+    for &word in words {
         suc_bv
-            .push_bits(rng.sample(Standard), 64)
+            .push_bits(word as usize, 64)
             .expect("Failed to push bits into sucds bitvector");
     }
 
     SucDArray::from_bits(suc_bv.iter()).enable_rank()
 }
 
-fn construct_bitm_vec(rng: &mut ThreadRng, len: usize) -> RankSelect101111<CombinedSampling> {
-    let mut bv = Box::<[u64]>::with_zeroed_bits(len);
-    for i in 0..len {
-        if rng.gen_bool(0.5) {
-            bv.set_bit(i);
-        }
-    }
-
-    bv.into()
+fn construct_bitm_vec(words: &[u64]) -> RankSelect101111<CombinedSampling> {
+    words.to_vec().into_boxed_slice().into()
 }
 
-fn construct_sux_rank9(rng: &mut ThreadRng, len: usize) -> Sux9 {
+fn construct_sux_rank9(words: &[u64], len: usize) -> Sux9 {
     let mut bit_vec = sux::prelude::BitVec::new(len);
-    for i in 0..len {
-        if rng.gen_bool(0.5) {
+    for (i, bit) in common::bits(words).enumerate() {
+        if bit {
             bit_vec.set(i, true)
         }
     }
@@ -119,10 +115,10 @@ fn construct_sux_rank9(rng: &mut ThreadRng, len: usize) -> Sux9 {
     Sux9::new(bit_vec)
 }
 
-fn construct_sux_small(rng: &mut ThreadRng, len: usize) -> RankSmall<1, 10, sux::bits::BitVec, Box<[usize]>, Box<[Block32Counters<1, 10>]>> {
+fn construct_sux_small(words: &[u64], len: usize) -> RankSmall<1, 10, sux::bits::BitVec, Box<[usize]>, Box<[Block32Counters<1, 10>]>> {
     let mut bit_vec = sux::prelude::BitVec::new(len);
-    for i in 0..len {
-        if rng.gen_bool(0.5) {
+    for (i, bit) in common::bits(words).enumerate() {
+        if bit {
             bit_vec.set(i, true)
         }
     }
@@ -131,24 +127,25 @@ fn construct_sux_small(rng: &mut ThreadRng, len: usize) -> RankSmall<1, 10, sux:
 }
 
 fn compare_ranks(b: &mut Criterion) {
-    let mut rng = rand::thread_rng();
+    let mut rng = common::seeded_rng();
 
     let mut group = b.benchmark_group("Rank: Randomized Input");
     group.plot_config(common::plot_config());
 
     for l in common::SIZES {
-        let vers_vec = common::construct_vers_vec(&mut rng, l);
-        let rsdict = construct_rsdict_vec(&mut rng, l);
-        let bio_vec = construct_bio_vec(&mut rng, l);
-        let fair_bio_vec = construct_fair_bio_vec(&mut rng, l);
-        let fid_vec = construct_fid_vec(&mut rng, l);
-        let ind_bit_vec = construct_ind_bit_vec(&mut rng, l);
-        let rank9_vec = construct_rank9_vec(&mut rng, l);
-        let sucds_vec = construct_sucds_vec(&mut rng, l);
-        let sucds_darray = construct_sucds_darray(&mut rng, l);
-        let bitm_vec = construct_bitm_vec(&mut rng, l);
-        let sux_rank9 = construct_sux_rank9(&mut rng, l);
-        let sux_small = construct_sux_small(&mut rng, l);
+        let words = common::random_words(l);
+        let vers_vec = common::construct_vers_vec(&words);
+        let rsdict = construct_rsdict_vec(&words, l);
+        let bio_vec = construct_bio_vec(&words, l);
+        let fair_bio_vec = construct_fair_bio_vec(&words, l);
+        let fid_vec = construct_fid_vec(&words);
+        let ind_bit_vec = construct_ind_bit_vec(&words, l);
+        let rank9_vec = construct_rank9_vec(&words, l);
+        let sucds_vec = construct_sucds_vec(&words, l);
+        let sucds_darray = construct_sucds_darray(&words, l);
+        let bitm_vec = construct_bitm_vec(&words);
+        let sux_rank9 = construct_sux_rank9(&words, l);
+        let sux_small = construct_sux_small(&words, l);
 
         let sample = Uniform::new(0, l);
 
@@ -252,22 +249,23 @@ fn compare_ranks(b: &mut Criterion) {
 }
 
 fn compare_selects(b: &mut Criterion) {
-    let mut rng = rand::thread_rng();
+    let mut rng = common::seeded_rng();
 
     let mut group = b.benchmark_group("Select: Randomized Input");
     group.plot_config(common::plot_config());
 
     for l in common::SIZES {
-        let vers_vec = common::construct_vers_vec(&mut rng, l);
-        let rsdict = construct_rsdict_vec(&mut rng, l);
-        let bio_vec = construct_bio_vec(&mut rng, l);
-        let fair_bio_vec = construct_fair_bio_vec(&mut rng, l);
-        let fid_vec = construct_fid_vec(&mut rng, l);
-        let ind_bit_vec = construct_ind_bit_vec(&mut rng, l);
-        let rank9_vec = construct_rank9_select_vec(&mut rng, l);
-        let sucds_vec = construct_sucds_vec(&mut rng, l);
-        let sucds_darray = construct_sucds_darray(&mut rng, l);
-        let bitm_vec = construct_bitm_vec(&mut rng, l);
+        let words = common::random_words(l);
+        let vers_vec = common::construct_vers_vec(&words);
+        let rsdict = construct_rsdict_vec(&words, l);
+        let bio_vec = construct_bio_vec(&words, l);
+        let fair_bio_vec = construct_fair_bio_vec(&words, l);
+        let fid_vec = construct_fid_vec(&words);
+        let ind_bit_vec = construct_ind_bit_vec(&words, l);
+        let rank9_vec = construct_rank9_select_vec(&words, l);
+        let sucds_vec = construct_sucds_vec(&words, l);
+        let sucds_darray = construct_sucds_darray(&words, l);
+        let bitm_vec = construct_bitm_vec(&words);
 
         let sample = Uniform::new(0,
                                   [
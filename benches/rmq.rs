@@ -1,17 +1,17 @@
 use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
 use librualg::segment_tree::RmqMin;
 use rand::distributions::{Distribution, Standard, Uniform};
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 mod common;
 
 fn bench_rmq(b: &mut Criterion) {
     let mut group = b.benchmark_group("RMQ: Randomized Input");
     group.plot_config(common::plot_config());
-    let mut rng = rand::thread_rng();
+    let mut rng = common::seeded_rng();
 
     for l in common::SIZES {
-        let sequence = thread_rng()
+        let sequence = common::input_rng(l)
             .sample_iter(Standard)
             .take(l)
             .collect::<Vec<u64>>();
@@ -2,17 +2,17 @@ use std::hint::black_box;
 use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId};
 use qwt::{AccessUnsigned, RankUnsigned};
 use rand::distributions::{Standard, Uniform, Distribution};
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 mod common;
 
 fn bench_access(c: &mut criterion::Criterion) {
     let mut group = c.benchmark_group("Wavelet Matrix: Random Access");
     group.plot_config(common::plot_config());
-    let mut rng = rand::thread_rng();
+    let mut rng = common::seeded_rng();
 
     for l in common::SIZES {
-        let sequence = thread_rng()
+        let sequence = common::input_rng(l)
             .sample_iter(Standard)
             .take(l >> 2)
             .collect::<Vec<u64>>();
@@ -77,10 +77,10 @@ fn bench_access(c: &mut criterion::Criterion) {
 fn bench_rank(c: &mut criterion::Criterion) {
     let mut group = c.benchmark_group("Wavelet Matrix: Rank");
     group.plot_config(common::plot_config());
-    let mut rng = rand::thread_rng();
+    let mut rng = common::seeded_rng();
 
     for l in common::SIZES {
-        let sequence = thread_rng()
+        let sequence = common::input_rng(l)
             .sample_iter(Standard)
             .take(l >> 2)
             .collect::<Vec<u64>>();
@@ -142,5 +142,5 @@ fn bench_rank(c: &mut criterion::Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_rank);
+criterion_group!(benches, bench_access, bench_rank);
 criterion_main!(benches);
@@ -0,0 +1,106 @@
+//! Records the build environment, so every run of the harness can report which compiler, flags and
+//! library versions produced its measurements.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Crates whose locked versions are recorded in the metadata of every run.
+const RECORDED_CRATES: &[&str] = &[
+    "vers-vecs",
+    "rsdict",
+    "bio",
+    "bv",
+    "fid",
+    "indexed_bitvec",
+    "succinct",
+    "elias-fano",
+    "range_minimum_query",
+    "librualg",
+    "sucds",
+    "bitm",
+    "cseq",
+    "wavelet-matrix",
+    "qwt",
+    "sux",
+    "rand",
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-env-changed=RUSTFLAGS");
+
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = command_output(Command::new(rustc).arg("--version")).unwrap_or_default();
+    set_env("VERS_BENCH_RUSTC_VERSION", &rustc_version);
+
+    let rustflags = env::var("CARGO_ENCODED_RUSTFLAGS").unwrap_or_default().replace('\x1f', " ");
+    set_env("VERS_BENCH_RUSTFLAGS", &rustflags);
+    set_env("VERS_BENCH_PROFILE", &env::var("PROFILE").unwrap_or_default());
+    set_env("VERS_BENCH_TARGET", &env::var("TARGET").unwrap_or_default());
+    set_env("VERS_BENCH_TARGET_FEATURES", &env::var("CARGO_CFG_TARGET_FEATURE").unwrap_or_default());
+
+    watch_vers_head();
+    let vers_commit = command_output(Command::new("git").args(["-C", "vers", "rev-parse", "HEAD"])).unwrap_or_default();
+    let vers_dirty = command_output(Command::new("git").args(["-C", "vers", "status", "--porcelain"])).is_some_and(|status| !status.is_empty());
+    set_env("VERS_BENCH_VERS_COMMIT", &format!("{vers_commit}{}", if vers_dirty { "-dirty" } else { "" }));
+
+    // the dependency line of vers, which contains its enabled features
+    let manifest = fs::read_to_string("Cargo.toml").unwrap_or_default();
+    let vers_dependency = manifest.lines().find(|line| line.starts_with("vers-vecs")).unwrap_or_default();
+    set_env("VERS_BENCH_VERS_DEPENDENCY", vers_dependency.trim());
+
+    let lock = fs::read_to_string("Cargo.lock").unwrap_or_default();
+    set_env("VERS_BENCH_CRATE_VERSIONS", &locked_versions(&lock).join(";"));
+}
+
+/// Rerun the build script when the checked out commit of vers changes. In a submodule checkout `vers/.git`
+/// is a file pointing into the git directory of the superproject, so the git directory is asked from git.
+/// Only existing paths are watched, as cargo reruns the script on every build for a missing one.
+fn watch_vers_head() {
+    let Some(git_dir) = command_output(Command::new("git").args(["-C", "vers", "rev-parse", "--absolute-git-dir"])) else {
+        return;
+    };
+    let branch = command_output(Command::new("git").args(["-C", "vers", "symbolic-ref", "-q", "HEAD"]));
+    for file in std::iter::once("HEAD".to_string()).chain(branch) {
+        let path = Path::new(&git_dir).join(file);
+        if path.exists() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+}
+
+fn set_env(key: &str, value: &str) {
+    println!("cargo:rustc-env={key}={value}");
+}
+
+/// Run a command and return its trimmed standard output, if it succeeded.
+fn command_output(command: &mut Command) -> Option<String> {
+    let output = command.output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Find the locked versions of all recorded crates as `name=version` pairs. A crate locked in several
+/// versions is listed once per version.
+fn locked_versions(lock: &str) -> Vec<String> {
+    let mut versions = Vec::new();
+    for package in lock.split("[[package]]").skip(1) {
+        let field = |key: &str| {
+            package
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.trim().strip_prefix("= ")?.strip_prefix('"')?.strip_suffix('"'))
+        };
+        if let (Some(name), Some(version)) = (field("name"), field("version")) {
+            if RECORDED_CRATES.contains(&name) {
+                versions.push(format!("{name}={version}"));
+            }
+        }
+    }
+    versions
+}
@@ -0,0 +1,5 @@
+#import "lib.typ": suite, template
+
+#show: template
+
+#suite("../measurements/elias_fano_in_order.csv")
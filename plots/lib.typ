@@ -0,0 +1,118 @@
+// Shared helpers for plotting the result files of the harness. Columns are read by name, so the
+// scripts keep working when the harness appends columns to its schema.
+//
+// Paths passed to these functions are relative to this directory.
+
+#import "@preview/lilaq:0.3.0" as lq
+
+// The ratio between the slowest and the fastest runner above which they are split into two plots.
+#let default-split-ratio = 4.0
+
+// Page and diagram setup for all plot documents, applied with `#show: template`.
+#let template(body) = {
+    set page(width: auto, height: auto, margin: .5cm)
+    show: lq.set-diagram(width: 21cm, height: 14.8cm)
+    set lq.mark(align: lq.marks.a3)
+    body
+}
+
+// The value of a numeric column, or `fallback` if the row has no value for it.
+#let number(row, column, fallback) = {
+    let value = row.at(column, default: "")
+    if value == "" { fallback } else { float(value) }
+}
+
+// Load the measured rows of the given kind from a result file. Only rows of a single run are kept,
+// by default those of the last run in the file.
+#let load(path, kind: "query", run: auto) = {
+    let rows = csv(path, row-type: dictionary)
+    let run = if run != auto { run } else if rows.len() > 0 { rows.last().at("run_id", default: "") } else { "" }
+    rows.filter(row => row.kind == kind and row.status == "ok" and row.at("run_id", default: "") == run)
+}
+
+// Group rows into one line per runner, with the confidence interval of the mean as error band.
+#let series(rows) = {
+    let lines = (:)
+    for row in rows {
+        let line = lines.at(row.runner, default: (x: (), mean: (), low: (), high: ()))
+        let mean = float(row.mean_ns)
+        line.x.push(int(row.size))
+        line.mean.push(mean)
+        line.low.push(number(row, "ci_lower_ns", mean))
+        line.high.push(number(row, "ci_upper_ns", mean))
+        lines.insert(row.runner, line)
+    }
+    lines
+}
+
+// The geometric mean of a line over all sizes, which weighs every size equally.
+#let score(line) = {
+    let positive = line.mean.filter(mean => mean > 0)
+    if positive.len() == 0 { 0.0 } else { calc.exp(positive.map(calc.ln).sum() / positive.len()) }
+}
+
+// Split the lines into fast and slow runners at the largest gap between their scores, if the slowest
+// runner is more than `ratio` times slower than the fastest. The reference runner is shown in both
+// plots. Returns an array of (part, lines) pairs, where the part is `none` if the lines were not split.
+#let split(lines, ratio: default-split-ratio, reference: "Vers") = {
+    let scores = lines.values().map(score).filter(score => score > 0).sorted()
+    if scores.len() < 2 or scores.last() <= scores.first() * ratio {
+        return ((none, lines),)
+    }
+
+    let gaps = range(scores.len() - 1).map(i => scores.at(i + 1) / scores.at(i))
+    let threshold = scores.at(gaps.position(gap => gap == calc.max(..gaps)))
+
+    let fast = (:)
+    let slow = (:)
+    for (name, line) in lines.pairs() {
+        if score(line) <= threshold { fast.insert(name, line) } else { slow.insert(name, line) }
+    }
+    if reference in fast {
+        slow.insert(reference, fast.at(reference))
+    } else if reference in slow {
+        fast.insert(reference, slow.at(reference))
+    }
+
+    (("fastest", fast), ("slowest", slow))
+}
+
+// Draw the lines with their error bands into one diagram. The colors map every runner to its color,
+// so a runner has the same color in all diagrams of a kind.
+#let diagram(title, lines, colors, ylabel: "Time per query (ns)") = lq.diagram(
+    title: title,
+    xlabel: "Elements",
+    ylabel: ylabel,
+    xscale: "log",
+    ylim: (0, auto),
+
+    ..for (name, line) in lines.pairs() {
+        let color = colors.at(name)
+        (
+            lq.fill-between(line.x, line.low, y2: line.high, fill: color.transparentize(75%)),
+            lq.plot(line.x, line.mean, color: color, label: name, stroke: (thickness: 2pt)),
+        )
+    }
+)
+
+// Plot every kind of a result file, splitting fast and slow runners into separate diagrams. Every
+// density of a density sweep is plotted separately.
+#let suite(path, kinds: ("query",), ratio: default-split-ratio, reference: "Vers") = {
+    let palette = lq.color.map.petroff8
+    for kind in kinds {
+        let all-rows = load(path, kind: kind)
+        for density in all-rows.map(row => row.at("density", default: "")).dedup() {
+            let rows = all-rows.filter(row => row.at("density", default: "") == density)
+            let lines = series(rows)
+            let colors = lines.keys().enumerate().map(((i, name)) => (name, palette.at(calc.rem(i, palette.len())))).to-dict()
+            let ylabel = if kind == "build" { "Time per construction (ns)" } else { "Time per query (ns)" }
+            let name = rows.first().benchmark + (if density != "" { ", density " + density } else { "" })
+
+            for (part, group) in split(lines, ratio: ratio, reference: reference) {
+                let title = name + " (" + kind + (if part != none { ", " + part } else { "" }) + ")"
+                diagram(title, group, colors, ylabel: ylabel)
+                linebreak()
+            }
+        }
+    }
+}
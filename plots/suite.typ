@@ -0,0 +1,10 @@
+// Plot the results of any suite by its name, e.g.
+//   typst compile plots/suite.typ plots/select.pdf --input suite=select --input kinds=query,chain
+#import "lib.typ": suite, template
+
+#show: template
+
+#let name = sys.inputs.at("suite", default: "rank")
+#let kinds = sys.inputs.at("kinds", default: "query").split(",")
+
+#suite("../measurements/" + name + ".csv", kinds: kinds)
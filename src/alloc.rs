@@ -0,0 +1,83 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs;
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
+
+/// Whether allocations are currently tracked. Outside of [`track_peak`] the allocator only pays for this load,
+/// so timed code is not skewed by the bookkeeping.
+static TRACKING: AtomicBool = AtomicBool::new(false);
+
+/// The net number of bytes allocated since tracking started. Freeing memory allocated before that makes it negative.
+static ALLOCATED: AtomicIsize = AtomicIsize::new(0);
+
+/// The maximum of [`ALLOCATED`] since tracking started.
+static PEAK: AtomicIsize = AtomicIsize::new(0);
+
+/// A global allocator forwarding to the system allocator, which can track the peak heap usage of a closure,
+/// so the harness can estimate how much memory a runner needs to build its context.
+pub(crate) struct TrackingAllocator;
+
+impl TrackingAllocator {
+    fn add(size: usize) {
+        if TRACKING.load(Ordering::Relaxed) {
+            let current = ALLOCATED.fetch_add(size as isize, Ordering::Relaxed) + size as isize;
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+    }
+
+    fn sub(size: usize) {
+        if TRACKING.load(Ordering::Relaxed) {
+            ALLOCATED.fetch_sub(size as isize, Ordering::Relaxed);
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::add(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::sub(layout.size());
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::add(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::add(new_size);
+            Self::sub(layout.size());
+        }
+        new_ptr
+    }
+}
+
+/// Run `f` with allocation tracking enabled, and return its result together with the peak number of bytes
+/// it held on the heap at once. Allocations of other threads during the call are counted as well.
+pub(crate) fn track_peak<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    ALLOCATED.store(0, Ordering::Relaxed);
+    PEAK.store(0, Ordering::Relaxed);
+    TRACKING.store(true, Ordering::SeqCst);
+    let result = f();
+    TRACKING.store(false, Ordering::SeqCst);
+    (result, PEAK.load(Ordering::Relaxed).max(0) as usize)
+}
+
+/// The total physical memory of the machine in bytes, if it can be determined.
+pub(crate) fn total_memory() -> Option<usize> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kilobytes = line.split_whitespace().nth(1)?.parse::<usize>().ok()?;
+    Some(kilobytes * 1024)
+}
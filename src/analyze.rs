@@ -0,0 +1,52 @@
+//! Offline re-analysis of raw sample files written with `--raw-samples`. The statistics of every row
+//! are recomputed with the current statistics module, so results of earlier runs can be re-evaluated
+//! without measuring again.
+
+use crate::benchmark::label;
+use crate::cli::AnalyzeArgs;
+use crate::measure::MeasurementKind;
+use crate::perf::EVENTS;
+use crate::results;
+use crate::sink::{CsvSink, ResultRow, ResultSink, Row, Run, Status};
+
+/// Recompute and print the statistics of all rows of a raw sample file, and write them in the result
+/// schema if an output file is given.
+pub(crate) fn analyze(args: &AnalyzeArgs) -> Result<(), String> {
+    let records = results::read(&args.input).map_err(|e| format!("Could not read {}: {}", args.input.display(), e))?;
+    let mut sink = args
+        .output
+        .as_deref()
+        .map(|output| CsvSink::create_file(output, ResultRow::COLUMNS))
+        .transpose()
+        .map_err(|e| format!("Could not create the output file: {}", e))?;
+
+    for record in &records {
+        let Some(stats) = record.statistics_from_samples() else {
+            eprintln!("WARNING: Skipping {} ({}) at size {} without samples", record.text("runner"), record.text("kind"), record.text("size"));
+            continue;
+        };
+        let (benchmark, runner, size) = (record.text("benchmark"), record.text("runner"), record.text("size"));
+        let kind = record.text("kind").parse::<MeasurementKind>()?;
+        let density = record.number("density");
+
+        println!("[{}/{}]\t{} ({})\tMean: {:.6} [{:.6}-{:.6} CI],\t Median: {:.6} (MAD {:.6}),\t [{:.6}-{:.6}],\t Std. Dev: {:.6} ({:.3}%),\t Outliers: {} mild, {} severe of {}",
+                 benchmark, size, label(runner, density), kind, stats.mean, stats.ci_lower, stats.ci_upper, stats.median, stats.mad, stats.min, stats.max, stats.std_dev, stats.relative_std_dev() * 100.0, stats.mild_outliers, stats.severe_outliers, stats.samples);
+
+        if let Some(sink) = sink.as_mut() {
+            let row = ResultRow {
+                benchmark,
+                runner,
+                size: size.parse().map_err(|e| format!("invalid size {}: {}", size, e))?,
+                density,
+                kind,
+                status: Status::Ok,
+                seed: record.text("seed").parse().unwrap_or_default(),
+                stats: Some(&stats),
+                counters: [None; EVENTS],
+            };
+            let run = Run { id: record.text("run_id"), metadata: record.text("metadata") };
+            sink.write(&row.values(run)).map_err(|e| format!("Could not write the output file: {}", e))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,242 @@
+//! Worst-case bit vectors for the select structures of the compared libraries. Each [`Pattern`]
+//! targets a sampling strategy, and its layout is computed in closed form, so the number of selectable
+//! bits is known without building the vector.
+//!
+//! All patterns are parameterized by the size in bits, which should be a multiple of 64, and by a
+//! density whose meaning depends on the pattern.
+//!
+//! The patterns target the select sampling of the libraries as follows:
+//!
+//! | Library              | Select support                              | Pattern                             |
+//! |----------------------|---------------------------------------------|-------------------------------------|
+//! | Vers, RsDict, SDSR9  | a sample every fixed number of ones         | [`Pattern::DenseBlock`]             |
+//! | SDSDA                | sucds `DArray`, every 32nd one per block    | [`Pattern::SpreadGroups`]           |
+//! | SuxR9, SuxSmall      | sux `SelectAdapt`, spacing by density       | [`Pattern::AlternatingSuperblocks`] |
+//! | Bitm                 | bitm `CombinedSampling`, spacing by density | [`Pattern::AlternatingSuperblocks`] |
+//! | Bio, FID, IBV, SctR9 | a search of the rank counters               | none, they serve as a reference     |
+//!
+//! The zero samples of all libraries are targeted by [`Pattern::IsolatedZeros`].
+
+use std::ops::Range;
+
+/// The number of ones covered by one select sample of vers, and the size of the superblocks of the
+/// alternating pattern.
+pub(crate) const SUPERBLOCK_BITS: usize = 1 << 13;
+
+const SUPERBLOCK_WORDS: usize = SUPERBLOCK_BITS / 64;
+
+/// The number of ones per group of [`Pattern::SpreadGroups`], which is the distance between two
+/// samples of the dense blocks of sucds `DArray`.
+const GROUP_ONES: usize = 32;
+
+/// A worst-case bit pattern for select queries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Pattern {
+    /// A block of [`SUPERBLOCK_BITS`] ones followed by long runs of zeros, each ending in a single one.
+    /// The density is the fraction of ones after the block. The sparse ones share a select sample
+    /// with the dense block, so samples every fixed number of ones (vers, RsDict) have to scan the
+    /// zero runs. Queries select the sparse ones.
+    DenseBlock,
+
+    /// Superblocks of [`SUPERBLOCK_BITS`] bits, alternately dense and sparse. The density is the
+    /// fraction of ones in the dense superblocks, while every sparse superblock holds a single one in
+    /// its last bit. This defeats strategies that adapt their sampling to the average density
+    /// (sux `SelectAdapt`, bitm `CombinedSampling`, the dense/sparse blocks of sucds `DArray`).
+    /// Queries select uniformly random ones.
+    AlternatingSuperblocks,
+
+    /// Groups of words, each with a single one in its first bit and the remaining ones of the group in
+    /// the highest bits of its last word, so every [`GROUP_ONES`] ones span a whole group. The density is
+    /// the fraction of ones, which sets the length of the groups. The dense blocks of sucds `DArray`
+    /// sample every 32nd one and scan the words from the sample to the queried one. At the default
+    /// density, a block of 1024 ones spans just below the 2^16 bits above which `DArray` stores all
+    /// positions instead. Queries select uniformly random ones.
+    SpreadGroups,
+
+    /// All ones except isolated zeros, each in the last bit of a long run of ones. The density is the
+    /// fraction of ones. Zero samples of all libraries span the long runs. Queries select the zeros.
+    IsolatedZeros,
+}
+
+/// All patterns in the order they are benchmarked.
+pub(crate) const PATTERNS: [Pattern; 4] = [Pattern::DenseBlock, Pattern::AlternatingSuperblocks, Pattern::SpreadGroups, Pattern::IsolatedZeros];
+
+impl Pattern {
+    /// The name of the pattern in benchmark names.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Pattern::DenseBlock => "Dense_Block",
+            Pattern::AlternatingSuperblocks => "Alternating",
+            Pattern::SpreadGroups => "Spread_Groups",
+            Pattern::IsolatedZeros => "Isolated_Zeros",
+        }
+    }
+
+    /// The density used if none is configured.
+    pub(crate) fn default_density(self) -> f64 {
+        match self {
+            Pattern::DenseBlock => 1.0 / 65536.0,
+            Pattern::AlternatingSuperblocks => 0.5,
+            Pattern::SpreadGroups => GROUP_ONES as f64 / (31.0 * 64.0),
+            Pattern::IsolatedZeros => 1.0 - 1.0 / 65536.0,
+        }
+    }
+
+    /// Whether queries select zeros rather than ones.
+    pub(crate) fn selects_zeros(self) -> bool {
+        self == Pattern::IsolatedZeros
+    }
+
+    /// Generate the words of the pattern with `size` bits, least significant bit first.
+    pub(crate) fn words(self, size: usize, density: f64) -> Vec<u64> {
+        let words = size / 64;
+        match self {
+            Pattern::DenseBlock => {
+                let run = run_words(density, words.saturating_sub(SUPERBLOCK_WORDS));
+                (0..words)
+                    .map(|i| match i.checked_sub(SUPERBLOCK_WORDS) {
+                        None => u64::MAX,
+                        Some(j) if (j + 1) % run == 0 => 1 << 63,
+                        Some(_) => 0,
+                    })
+                    .collect()
+            }
+            Pattern::AlternatingSuperblocks => {
+                let dense = dense_word(density);
+                (0..words)
+                    .map(|i| match (i / SUPERBLOCK_WORDS % 2, i % SUPERBLOCK_WORDS) {
+                        (0, _) => dense,
+                        (_, offset) if offset == SUPERBLOCK_WORDS - 1 => 1 << 63,
+                        _ => 0,
+                    })
+                    .collect()
+            }
+            Pattern::SpreadGroups => {
+                let group = group_words(density, words);
+                let last = u64::MAX << (64 - (GROUP_ONES - 1));
+                (0..words)
+                    .map(|i| match (i % group, group) {
+                        (_, 1) => 1 | last,
+                        (0, _) => 1,
+                        (offset, _) if offset == group - 1 => last,
+                        _ => 0,
+                    })
+                    .collect()
+            }
+            Pattern::IsolatedZeros => {
+                let run = run_words(1.0 - density, words);
+                (0..words).map(|i| if (i + 1) % run == 0 { u64::MAX >> 1 } else { u64::MAX }).collect()
+            }
+        }
+    }
+
+    /// The ranks queried on the pattern with `size` bits. All ranks in the range select a bit. If the
+    /// dense block of [`Pattern::DenseBlock`] fills the whole vector, its ones are queried instead.
+    pub(crate) fn query_ranks(self, size: usize, density: f64) -> Range<usize> {
+        let words = size / 64;
+        match self {
+            Pattern::DenseBlock => {
+                let block = words.min(SUPERBLOCK_WORDS) * 64;
+                let tail = words - words.min(SUPERBLOCK_WORDS);
+                match tail / run_words(density, tail) {
+                    0 => 0..block,
+                    sparse => block..block + sparse,
+                }
+            }
+            Pattern::AlternatingSuperblocks => {
+                // a trailing partial superblock is dense if it follows an even number of full ones,
+                // and a partial sparse superblock misses its last word with the single one
+                let (full, rest) = (words / SUPERBLOCK_WORDS, words % SUPERBLOCK_WORDS);
+                let dense_words = full.div_ceil(2) * SUPERBLOCK_WORDS + if full % 2 == 0 { rest } else { 0 };
+                0..dense_words * dense_word(density).count_ones() as usize + full / 2
+            }
+            Pattern::SpreadGroups => {
+                // a trailing partial group only holds its first one
+                let group = group_words(density, words);
+                0..words / group * GROUP_ONES + usize::from(!words.is_multiple_of(group))
+            }
+            Pattern::IsolatedZeros => 0..words / run_words(1.0 - density, words),
+        }
+    }
+}
+
+/// The length in words of the runs that end in a single isolated bit, such that the isolated bits
+/// have the given density. At least one run fits into `words`.
+fn run_words(density: f64, words: usize) -> usize {
+    ((1.0 / (density * 64.0)).round() as usize).clamp(1, words.max(1))
+}
+
+/// The length in words of the groups of [`Pattern::SpreadGroups`], such that the ones have the given
+/// density. At least one group fits into `words`.
+fn group_words(density: f64, words: usize) -> usize {
+    ((GROUP_ONES as f64 / (density * 64.0)).round() as usize).clamp(1, words.max(1))
+}
+
+/// A word with the given fraction of bits set, at least one.
+fn dense_word(density: f64) -> u64 {
+    let ones = ((density * 64.0).round() as u32).clamp(1, 64);
+    u64::MAX >> (64 - ones)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sizes with fewer words than a superblock, exactly one, and full superblocks followed by partial
+    /// ones after an even and an odd number of full superblocks.
+    const SIZES: [usize; 7] = [
+        64,
+        64 * 50,
+        SUPERBLOCK_BITS,
+        SUPERBLOCK_BITS + 64 * 7,
+        2 * SUPERBLOCK_BITS + 64 * 3,
+        3 * SUPERBLOCK_BITS + 64 * 10,
+        5 * SUPERBLOCK_BITS,
+    ];
+
+    /// The ranks of all selectable bits of the generated words, counted bit by bit.
+    fn counted_ranks(pattern: Pattern, words: &[u64]) -> Range<usize> {
+        let ones = |words: &[u64]| words.iter().map(|word| word.count_ones() as usize).sum::<usize>();
+        match pattern {
+            Pattern::DenseBlock => {
+                let block = ones(&words[..words.len().min(SUPERBLOCK_WORDS)]);
+                let total = ones(words);
+                if total > block {
+                    block..total
+                } else {
+                    0..total
+                }
+            }
+            Pattern::AlternatingSuperblocks | Pattern::SpreadGroups => 0..ones(words),
+            Pattern::IsolatedZeros => 0..words.len() * 64 - ones(words),
+        }
+    }
+
+    #[test]
+    fn query_ranks_match_generated_words() {
+        for pattern in PATTERNS {
+            for density in [pattern.default_density(), 0.01, 0.5, 0.99] {
+                for size in SIZES {
+                    let words = pattern.words(size, density);
+                    assert_eq!(words.len(), size / 64);
+                    assert_eq!(pattern.query_ranks(size, density), counted_ranks(pattern, &words), "{pattern:?} with {size} bits at density {density}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spread_groups_fill_darray_blocks() {
+        // 1024 ones, the block length of `DArray`, span just below 2^16 bits at the default density
+        let density = Pattern::SpreadGroups.default_density();
+        let words = Pattern::SpreadGroups.words(1 << 20, density);
+        let ones = (0..words.len() * 64).filter(|&bit| words[bit / 64] >> (bit % 64) & 1 == 1).take(1025).collect::<Vec<_>>();
+        assert!(ones[1023] - ones[0] < 1 << 16);
+        assert_eq!(ones[1024], 32 * 31 * 64, "the next block starts at the first one of the next group");
+
+        // every sampled one is followed by a long run of zeros
+        for sample in (0..1024).step_by(GROUP_ONES) {
+            assert!(ones[sample + 1] - ones[sample] > 29 * 64);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::benches::input::{bits, random_words};
+use crate::benches::structures::{Expected, LIBRARIES};
+use crate::measure::input_rng;
+use crate::settings::Settings;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// How many mismatches are printed per library before the rest is summarized.
+const MAX_REPORTED_MISMATCHES: usize = 5;
+
+/// Compute the reference answers for `number` random positions of the bit vector stored in `words`.
+fn expected_answers(words: &[u64], number: usize, rng: &mut StdRng) -> Vec<Expected> {
+    let mut zeros = Vec::new();
+    let mut ones = Vec::new();
+    for (i, bit) in bits(words).enumerate() {
+        if bit {
+            ones.push(i);
+        } else {
+            zeros.push(i);
+        }
+    }
+
+    let len = words.len() * 64;
+    (0..number)
+        .map(|_| {
+            let pos = rng.gen_range(0..len);
+            let rank1 = ones.partition_point(|&one| one < pos);
+            Expected {
+                pos,
+                rank0: pos - rank1,
+                rank1,
+                select0: zeros.get(pos).copied(),
+                select1: ones.get(pos).copied(),
+            }
+        })
+        .collect()
+}
+
+/// Check that all selected libraries agree with a naive reference on random bit vectors of a few sizes,
+/// generated from the global seed. Returns `false` if any library returned a wrong result.
+pub(crate) fn verify(settings: &Settings) -> bool {
+    let seed = settings.seed;
+    let mut correct = true;
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for size in [1 << 7, 1 << 12, 1 << 16] {
+        let words = random_words(size, &mut input_rng(seed, size));
+        let expected = expected_answers(&words, 1000, &mut rng);
+
+        for library in LIBRARIES.iter().filter(|library| settings.selects_runner(library.name())) {
+            let mismatches = library.verify(&words, &expected);
+            if mismatches.is_empty() {
+                continue;
+            }
+
+            correct = false;
+            eprintln!("[Correctness/{}]\t{} returned {} wrong results", size, library.name(), mismatches.len());
+            for mismatch in mismatches.iter().take(MAX_REPORTED_MISMATCHES) {
+                eprintln!("\t{}", mismatch);
+            }
+        }
+    }
+
+    correct
+}
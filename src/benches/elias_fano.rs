@@ -0,0 +1,407 @@
+use crate::benches::input::{chain_index, random_values};
+use crate::benchmark::Benchmark;
+use crate::measure::{AccessPattern, Measurement};
+use crate::runner;
+use crate::runner::IntoOutput;
+use crate::settings::Settings;
+use cseq::elias_fano::{Builder, Sequence};
+use elias_fano::EliasFano;
+use rand::distributions::Uniform;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::cell::RefCell;
+use std::io;
+use std::path::Path;
+use sucds::mii_sequences::{EliasFano as SucdsEliasFano, EliasFanoBuilder};
+use vers_vecs::EliasFanoVec;
+
+/// The names of all runners of the random access and in-order iteration suites.
+pub(crate) const ACCESS_RUNNERS: [&str; 4] = ["Vers", "Elias-Fano", "Sucds", "Cseq"];
+
+/// The names of all runners of the predecessor suites.
+pub(crate) const PREDECESSOR_RUNNERS: [&str; 3] = ["Vers", "Sucds", "Binary Search"];
+
+/// How many values of the adversarial predecessor input are placed far above all other values.
+const ADVERSARIAL_OUTLIERS: usize = 100;
+
+/// The maximum number of predecessor queries precomputed per sequence, so the queries stay in cache
+/// and the time to look them up does not grow with the size.
+const PREDECESSOR_QUERIES: usize = 1 << 16;
+
+const SIZES: [usize; 11] = [
+    1 << 8,
+    1 << 10,
+    1 << 12,
+    1 << 14,
+    1 << 16,
+    1 << 18,
+    1 << 20,
+    1 << 22,
+    1 << 24,
+    1 << 26,
+    1 << 28,
+];
+
+/// Generate `size` uniformly random values in sorted order. The largest values are clamped below
+/// `u64::MAX`, because cseq and sucds need the universe size `max + 1` to fit into 64 bits.
+fn sorted_values(size: usize, rng: &mut StdRng) -> Vec<u64> {
+    let mut values = random_values(size, rng);
+    values.sort_unstable();
+    for value in values.iter_mut().rev().take_while(|value| **value == u64::MAX) {
+        *value = u64::MAX - 1;
+    }
+    values
+}
+
+/// Generate `size` sorted values uniformly distributed in `0..size`. The first value is zero, so every
+/// predecessor query in that range has an answer. The size must not be zero.
+fn uniform_values(size: usize, rng: &mut StdRng) -> Vec<u64> {
+    let mut values = rng.sample_iter(Uniform::new(0, size as u64)).take(size).collect::<Vec<_>>();
+    values.sort_unstable();
+    values[0] = 0;
+    values
+}
+
+/// Generate sorted values like [`uniform_values`], but replace the largest ones by a small cluster far
+/// above all other values. This inflates the universe of the Elias-Fano encoding, so the lower bits
+/// dominate the representation of the dense values and the upper bits no longer narrow down queries.
+/// The size must exceed the number of outliers.
+fn adversarial_values(size: usize, rng: &mut StdRng) -> Vec<u64> {
+    let mut values = uniform_values(size - ADVERSARIAL_OUTLIERS, rng);
+    let mut outliers = rng
+        .sample_iter(Uniform::new(u64::MAX / 2 - 200, u64::MAX / 2 - 1))
+        .take(ADVERSARIAL_OUTLIERS)
+        .collect::<Vec<_>>();
+    outliers.sort_unstable();
+    values.extend(outliers);
+    values
+}
+
+/// The number of predecessor queries precomputed for a sequence of `len` values.
+fn predecessor_queries(len: usize) -> usize {
+    len.min(PREDECESSOR_QUERIES)
+}
+
+/// A predecessor data structure together with query values drawn from its sequence.
+pub(crate) struct PredecessorContext<P> {
+    structure: P,
+
+    /// Values of the sequence, sorted by their position and thus by value.
+    queries: Box<[u64]>,
+}
+
+/// Pair the sorted `values` with the values queried for their predecessors, which are elements of the
+/// sequence like in the original criterion benchmark. Small sequences query all of their values,
+/// larger ones the values at random positions.
+fn predecessor_input(values: Vec<u64>, rng: &mut StdRng) -> (Vec<u64>, Box<[u64]>) {
+    let queries = if values.len() <= PREDECESSOR_QUERIES {
+        values.clone().into_boxed_slice()
+    } else {
+        let mut positions = (0..PREDECESSOR_QUERIES).map(|_| rng.gen_range(0..values.len())).collect::<Vec<_>>();
+        positions.sort_unstable();
+        positions.into_iter().map(|position| values[position]).collect()
+    };
+    (values, queries)
+}
+
+fn build_predecessor_context<P>(input: (Vec<u64>, Box<[u64]>), build: impl FnOnce(Vec<u64>) -> P) -> PredecessorContext<P> {
+    let (values, queries) = input;
+    PredecessorContext { structure: build(values), queries }
+}
+
+/// Generate `number` indices of precomputed predecessor queries, following the access pattern. The
+/// queries are sorted by their position in the sequence, so the pattern determines the queried positions.
+fn create_predecessor_queries(number: usize, len: usize, pattern: AccessPattern, rng: &mut StdRng) -> Box<[usize]> {
+    pattern.positions(number, predecessor_queries(len), rng)
+}
+
+fn chain_predecessor_query(result: usize, step: usize, len: usize) -> usize {
+    chain_index(result, step, predecessor_queries(len))
+}
+
+fn build_elias_fano(values: &[u64]) -> RefCell<EliasFano> {
+    let mut ef = EliasFano::new(values[values.len() - 1], values.len() as u64);
+    ef.compress(values.iter());
+    RefCell::new(ef)
+}
+
+fn build_sucds(values: &[u64]) -> SucdsEliasFano {
+    let mut builder = EliasFanoBuilder::new(values[values.len() - 1] as usize + 1, values.len())
+        .expect("Failed to create sucds Elias-Fano builder");
+    builder
+        .extend(values.iter().map(|&value| value as usize))
+        .expect("Failed to extend sucds Elias-Fano builder");
+    builder.build()
+}
+
+fn build_cseq(values: &[u64]) -> Sequence {
+    let mut builder = Builder::new(values.len(), values[values.len() - 1] + 1);
+    builder.push_all(values.iter().copied());
+    builder.finish()
+}
+
+/// Every query of the in-order suite is one complete pass over the sequence, so the parameters carry
+/// no information. The runners report the time per element of a pass.
+fn create_passes(number: usize) -> Box<[()]> {
+    vec![(); number].into_boxed_slice()
+}
+
+runner!(
+    VersAccessRunner,
+    generate_input = |size, rng| {
+        sorted_values(size, rng)
+    },
+    build = |values: Vec<u64>| {
+        EliasFanoVec::from_slice(&values)
+    },
+    prepare_params = |number, len, pattern, rng| {
+        pattern.positions(number, len, rng)
+    },
+    execute = |ef: EliasFanoVec, index: usize| -> usize {
+        ef.get_unchecked(*index).into_output()
+    },
+    chain = |result, step, len| {
+        chain_index(result, step, len)
+    }
+);
+
+runner!(
+    EliasFanoAccessRunner,
+    generate_input = |size, rng| {
+        sorted_values(size, rng)
+    },
+    build = |values: Vec<u64>| {
+        build_elias_fano(&values)
+    },
+    prepare_params = |number, len, pattern, rng| {
+        pattern.positions(number, len, rng)
+    },
+    execute = |ef: RefCell<EliasFano>, index: usize| -> usize {
+        ef.borrow_mut().visit(*index as u64).into_output()
+    },
+    chain = |result, step, len| {
+        chain_index(result, step, len)
+    }
+);
+
+runner!(
+    SucdsAccessRunner,
+    generate_input = |size, rng| {
+        sorted_values(size, rng)
+    },
+    build = |values: Vec<u64>| {
+        build_sucds(&values)
+    },
+    prepare_params = |number, len, pattern, rng| {
+        pattern.positions(number, len, rng)
+    },
+    execute = |ef: SucdsEliasFano, index: usize| -> usize {
+        ef.select(*index).into_output()
+    },
+    chain = |result, step, len| {
+        chain_index(result, step, len)
+    }
+);
+
+runner!(
+    CseqAccessRunner,
+    generate_input = |size, rng| {
+        sorted_values(size, rng)
+    },
+    build = |values: Vec<u64>| {
+        build_cseq(&values)
+    },
+    prepare_params = |number, len, pattern, rng| {
+        pattern.positions(number, len, rng)
+    },
+    execute = |ef: Sequence, index: usize| -> usize {
+        ef.get(*index).into_output()
+    },
+    chain = |result, step, len| {
+        chain_index(result, step, len)
+    }
+);
+
+runner!(
+    VersIterationRunner,
+    generate_input = |size, rng| {
+        sorted_values(size, rng)
+    },
+    build = |values: Vec<u64>| {
+        EliasFanoVec::from_slice(&values)
+    },
+    prepare_params = |number, _len, _pattern, _rng| {
+        create_passes(number)
+    },
+    execute = |ef: EliasFanoVec, _pass: ()| -> usize {
+        ef.iter().fold(0u64, |sum, value| sum.wrapping_add(value)).into_output()
+    },
+    elements_per_query = |size| size
+);
+
+runner!(
+    EliasFanoIterationRunner,
+    generate_input = |size, rng| {
+        sorted_values(size, rng)
+    },
+    build = |values: Vec<u64>| {
+        build_elias_fano(&values)
+    },
+    prepare_params = |number, _len, _pattern, _rng| {
+        create_passes(number)
+    },
+    execute = |ef: RefCell<EliasFano>, _pass: ()| -> usize {
+        let mut ef = ef.borrow_mut();
+        ef.reset();
+        let mut sum = 0u64;
+        while let Ok(value) = ef.next() {
+            sum = sum.wrapping_add(value);
+        }
+        sum.into_output()
+    },
+    elements_per_query = |size| size
+);
+
+runner!(
+    SucdsIterationRunner,
+    generate_input = |size, rng| {
+        sorted_values(size, rng)
+    },
+    build = |values: Vec<u64>| {
+        build_sucds(&values)
+    },
+    prepare_params = |number, _len, _pattern, _rng| {
+        create_passes(number)
+    },
+    execute = |ef: SucdsEliasFano, _pass: ()| -> usize {
+        ef.iter(0).fold(0usize, |sum, value| sum.wrapping_add(value))
+    },
+    elements_per_query = |size| size
+);
+
+runner!(
+    CseqIterationRunner,
+    generate_input = |size, rng| {
+        sorted_values(size, rng)
+    },
+    build = |values: Vec<u64>| {
+        build_cseq(&values)
+    },
+    prepare_params = |number, _len, _pattern, _rng| {
+        create_passes(number)
+    },
+    execute = |ef: Sequence, _pass: ()| -> usize {
+        ef.iter().fold(0u64, |sum, value| sum.wrapping_add(value)).into_output()
+    },
+    elements_per_query = |size| size
+);
+
+/// Define the predecessor runners of all libraries on the input generated by `$generate`, which needs
+/// at least `$min_size` values. The runners query the precomputed values of their [`PredecessorContext`].
+macro_rules! predecessor_runners {
+    ($vers:ident, $sucds:ident, $binary_search:ident, $generate:ident, $min_size:expr) => {
+        runner!(
+            $vers,
+            generate_input = |size, rng| {
+                predecessor_input($generate(size, rng), rng)
+            },
+            build = |input: (Vec<u64>, Box<[u64]>)| {
+                build_predecessor_context(input, |values| EliasFanoVec::from_slice(&values))
+            },
+            prepare_params = |number, len, pattern, rng| {
+                create_predecessor_queries(number, len, pattern, rng)
+            },
+            execute = |context: PredecessorContext<EliasFanoVec>, index: usize| -> usize {
+                context.structure.predecessor_unchecked(context.queries[*index]).into_output()
+            },
+            chain = |result, step, len| {
+                chain_predecessor_query(result, step, len)
+            },
+            min_size = $min_size
+        );
+
+        runner!(
+            $sucds,
+            generate_input = |size, rng| {
+                predecessor_input($generate(size, rng), rng)
+            },
+            build = |input: (Vec<u64>, Box<[u64]>)| {
+                build_predecessor_context(input, |values| build_sucds(&values).enable_rank())
+            },
+            prepare_params = |number, len, pattern, rng| {
+                create_predecessor_queries(number, len, pattern, rng)
+            },
+            execute = |context: PredecessorContext<SucdsEliasFano>, index: usize| -> usize {
+                context.structure.predecessor(context.queries[*index] as usize).into_output()
+            },
+            chain = |result, step, len| {
+                chain_predecessor_query(result, step, len)
+            },
+            min_size = $min_size
+        );
+
+        runner!(
+            $binary_search,
+            generate_input = |size, rng| {
+                predecessor_input($generate(size, rng), rng)
+            },
+            build = |input: (Vec<u64>, Box<[u64]>)| {
+                build_predecessor_context(input, |values| values)
+            },
+            prepare_params = |number, len, pattern, rng| {
+                create_predecessor_queries(number, len, pattern, rng)
+            },
+            execute = |context: PredecessorContext<Vec<u64>>, index: usize| -> usize {
+                let (values, value) = (&context.structure, context.queries[*index]);
+                values[values.partition_point(|&x| x <= value) - 1].into_output()
+            },
+            chain = |result, step, len| {
+                chain_predecessor_query(result, step, len)
+            },
+            min_size = $min_size
+        );
+    };
+}
+
+predecessor_runners!(VersUniformRunner, SucdsUniformRunner, BinarySearchUniformRunner, uniform_values, 1);
+predecessor_runners!(VersAdversarialRunner, SucdsAdversarialRunner, BinarySearchAdversarialRunner, adversarial_values, ADVERSARIAL_OUTLIERS + 1);
+
+/// Random access to the elements of sorted uniformly random sequences at uniformly random indices.
+pub(crate) fn random_access(output_dir: &Path, settings: &Settings) -> io::Result<()> {
+    let mut benchmark = Benchmark::new("Elias_Fano_Random", SIZES.to_vec()).with_settings(settings);
+    benchmark.add_measurement(Measurement::new(ACCESS_RUNNERS[0], &VersAccessRunner));
+    benchmark.add_measurement(Measurement::new(ACCESS_RUNNERS[1], &EliasFanoAccessRunner));
+    benchmark.add_measurement(Measurement::new(ACCESS_RUNNERS[2], &SucdsAccessRunner));
+    benchmark.add_measurement(Measurement::new(ACCESS_RUNNERS[3], &CseqAccessRunner));
+    benchmark.benchmark(output_dir)
+}
+
+/// Complete in-order passes over sorted uniformly random sequences. Each query is one pass, and the
+/// time is reported per element, so it is comparable across sizes.
+pub(crate) fn in_order(output_dir: &Path, settings: &Settings) -> io::Result<()> {
+    let mut benchmark = Benchmark::new("Elias_Fano_In_Order", SIZES.to_vec()).with_settings(settings);
+    benchmark.add_measurement(Measurement::new(ACCESS_RUNNERS[0], &VersIterationRunner));
+    benchmark.add_measurement(Measurement::new(ACCESS_RUNNERS[1], &EliasFanoIterationRunner));
+    benchmark.add_measurement(Measurement::new(ACCESS_RUNNERS[2], &SucdsIterationRunner));
+    benchmark.add_measurement(Measurement::new(ACCESS_RUNNERS[3], &CseqIterationRunner));
+    benchmark.benchmark(output_dir)
+}
+
+/// Predecessor queries for values of sequences of uniformly random values.
+pub(crate) fn predecessor_uniform(output_dir: &Path, settings: &Settings) -> io::Result<()> {
+    let mut benchmark = Benchmark::new("Elias_Fano_Pred_Random", SIZES.to_vec()).with_settings(settings);
+    benchmark.add_measurement(Measurement::new(PREDECESSOR_RUNNERS[0], &VersUniformRunner));
+    benchmark.add_measurement(Measurement::new(PREDECESSOR_RUNNERS[1], &SucdsUniformRunner));
+    benchmark.add_measurement(Measurement::new(PREDECESSOR_RUNNERS[2], &BinarySearchUniformRunner));
+    benchmark.benchmark(output_dir)
+}
+
+/// Predecessor queries like [`predecessor_uniform`], on sequences whose largest values are far
+/// outliers.
+pub(crate) fn predecessor_adversarial(output_dir: &Path, settings: &Settings) -> io::Result<()> {
+    let mut benchmark = Benchmark::new("Elias_Fano_Pred_Adversarial", SIZES.to_vec()).with_settings(settings);
+    benchmark.add_measurement(Measurement::new(PREDECESSOR_RUNNERS[0], &VersAdversarialRunner));
+    benchmark.add_measurement(Measurement::new(PREDECESSOR_RUNNERS[1], &SucdsAdversarialRunner));
+    benchmark.add_measurement(Measurement::new(PREDECESSOR_RUNNERS[2], &BinarySearchAdversarialRunner));
+    benchmark.benchmark(output_dir)
+}
@@ -0,0 +1,85 @@
+use rand::distributions::{Standard, Uniform};
+use rand::rngs::StdRng;
+use rand::Rng;
+
+/// Generate the words of a uniformly random bit vector with `size` bits from the given rng.
+/// `size` is expected to be a multiple of 64.
+pub(crate) fn random_words(size: usize, rng: &mut StdRng) -> Vec<u64> {
+    let sample = Uniform::new_inclusive(0, u64::MAX);
+    rng.sample_iter(sample).take(size / 64).collect()
+}
+
+/// The number of one bits of a bit vector with `size` bits and the given density. The vector has at
+/// least one one and one zero, so both select queries have valid ranks.
+pub(crate) fn ones_for_density(size: usize, density: f64) -> usize {
+    ((size as f64 * density).round() as usize).clamp(1, size - 1)
+}
+
+/// Generate the words of a random bit vector with `size` bits, of which exactly
+/// [`ones_for_density`] are ones. Every bit is first set independently with the density, and then
+/// random bits are flipped until the number of ones matches. With a density of 0.5, the bits before
+/// flipping are those of [`random_words`].
+pub(crate) fn random_words_with_density(size: usize, density: f64, rng: &mut StdRng) -> Vec<u64> {
+    // combine uniformly random words with `and` and `or` following the binary digits of the density,
+    // starting with the least significant one, so every bit is set with the density
+    let fixed = ((density * (1u64 << 32) as f64).round() as u64).clamp(1, (1 << 32) - 1);
+    let mut words = (0..size / 64)
+        .map(|_| {
+            (fixed.trailing_zeros()..32).fold(0, |word, digit| {
+                if (fixed >> digit) & 1 == 1 {
+                    word | rng.gen::<u64>()
+                } else {
+                    word & rng.gen::<u64>()
+                }
+            })
+        })
+        .collect::<Vec<u64>>();
+
+    let target = ones_for_density(words.len() * 64, density);
+    let mut ones = words.iter().map(|word| word.count_ones() as usize).sum::<usize>();
+    while ones != target {
+        let pos = rng.gen_range(0..words.len() * 64);
+        let bit = 1 << (pos % 64);
+        let set = words[pos / 64] & bit != 0;
+        if ones < target && !set {
+            words[pos / 64] |= bit;
+            ones += 1;
+        } else if ones > target && set {
+            words[pos / 64] &= !bit;
+            ones -= 1;
+        }
+    }
+    words
+}
+
+/// Generate `size` uniformly random values from the given rng.
+pub(crate) fn random_values(size: usize, rng: &mut StdRng) -> Vec<u64> {
+    rng.sample_iter(Standard).take(size).collect()
+}
+
+/// Iterate over the bits of a bit vector stored in `words`, least significant bit first.
+pub(crate) fn bits(words: &[u64]) -> impl Iterator<Item = bool> + '_ {
+    words
+        .iter()
+        .flat_map(|&word| (0..64).map(move |i| (word >> i) & 1 == 1))
+}
+
+/// Scramble a query result into a pseudo-random value using the splitmix64 finalizer.
+/// Used to derive the next query of a dependent chain from the previous result.
+pub(crate) fn scramble(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Map a uniformly distributed value into `0..bound` without the cost of a division.
+pub(crate) fn reduce(value: u64, bound: u64) -> u64 {
+    ((value as u128 * bound as u128) >> 64) as u64
+}
+
+/// Derive the next index in `0..len` of a dependent chain from the previous result and the position
+/// in the chain.
+pub(crate) fn chain_index(result: usize, step: usize, len: usize) -> usize {
+    reduce(scramble(result as u64 ^ step as u64), len as u64) as usize
+}
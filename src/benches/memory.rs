@@ -0,0 +1,41 @@
+use crate::benches::input::random_words;
+use crate::benches::structures::LIBRARIES;
+use crate::measure::input_rng;
+use crate::settings::Settings;
+use crate::sink::{MemoryRow, Sinks};
+use std::io;
+use std::path::Path;
+
+/// Compare the heap sizes of all libraries reporting them, and write the size and the overhead
+/// relative to the raw bit vector to the `memory` files. Each library is measured as built by the rank suite
+/// and as built by the select suites, which may add select indices.
+pub(crate) fn benchmark(output_dir: &Path, settings: &Settings) -> io::Result<()> {
+    let mut sinks = Sinks::create::<MemoryRow>(output_dir, "memory", settings)?;
+
+    let sizes = (12..=28).step_by(2).map(|exp| 1usize << exp).collect::<Vec<_>>();
+    for size in settings.sizes(&sizes) {
+        let words = random_words(size, &mut input_rng(settings.seed, size));
+        for library in LIBRARIES.iter().filter(|library| settings.selects_runner(library.name())) {
+            let heap_sizes = [("rank", library.rank_heap_size(&words)), ("select", library.select_heap_size(&words))];
+            for (support, heap_size) in heap_sizes {
+                let Some(heap_size) = heap_size else {
+                    continue;
+                };
+
+                let overhead = 100.0 * heap_size as f64 / (size / 8) as f64 - 100.0;
+                println!("[Memory/{}]\t{} ({})\t{} bytes ({:.2}% overhead)", size, library.name(), support, heap_size, overhead);
+                sinks.write(&MemoryRow {
+                    benchmark: "Memory",
+                    runner: library.name(),
+                    support,
+                    size,
+                    heap_bytes: heap_size,
+                    overhead_percent: overhead,
+                    seed: settings.seed,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
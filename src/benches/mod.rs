@@ -1,2 +1,124 @@
+use crate::benches::structures::library_names;
+use crate::settings::Settings;
+use std::path::Path;
+
+pub(crate) mod adversarial;
+pub(crate) mod correctness;
+pub(crate) mod elias_fano;
+pub(crate) mod input;
+pub(crate) mod memory;
 pub(crate) mod rank;
-pub(crate) mod select;
\ No newline at end of file
+pub(crate) mod rmq;
+pub(crate) mod select;
+pub(crate) mod select_adversarial;
+pub(crate) mod structures;
+pub(crate) mod wavelet;
+
+/// A benchmark suite that can be selected on the command line.
+pub(crate) struct Suite {
+    pub(crate) name: &'static str,
+
+    pub(crate) description: &'static str,
+
+    /// The names of the runners measured by the suite, which can be selected on the command line.
+    pub(crate) runners: fn() -> Vec<&'static str>,
+
+    /// Run the suite and write its results into the output directory.
+    pub(crate) run: fn(&Path, &Settings) -> Result<(), String>,
+}
+
+/// All suites in the order they are run.
+pub(crate) static SUITES: &[Suite] = &[
+    Suite {
+        name: "correctness",
+        description: "Verify all rank/select libraries against a naive reference",
+        runners: library_names,
+        run: |_, settings| {
+            if correctness::verify(settings) {
+                Ok(())
+            } else {
+                Err("Some libraries returned wrong results, aborting the benchmark.".to_string())
+            }
+        },
+    },
+    Suite {
+        name: "rank",
+        description: "rank0 at uniformly random positions",
+        runners: library_names,
+        run: |output_dir, settings| {
+            rank::benchmark(output_dir, settings).map_err(|e| format!("Could not write the rank results: {}", e))
+        },
+    },
+    Suite {
+        name: "select",
+        description: "select0 with uniformly random ranks",
+        runners: library_names,
+        run: |output_dir, settings| {
+            select::benchmark(output_dir, settings).map_err(|e| format!("Could not write the select results: {}", e))
+        },
+    },
+    Suite {
+        name: "select-adversarial",
+        description: "select on worst-case bit patterns of the sampling strategies of all libraries",
+        runners: library_names,
+        run: |output_dir, settings| {
+            select_adversarial::benchmark(output_dir, settings).map_err(|e| format!("Could not write the adversarial select results: {}", e))
+        },
+    },
+    Suite {
+        name: "rmq",
+        description: "Range minimum queries on uniformly random ranges",
+        runners: || rmq::RUNNERS.to_vec(),
+        run: |output_dir, settings| {
+            rmq::benchmark(output_dir, settings).map_err(|e| format!("Could not write the rmq results: {}", e))
+        },
+    },
+    Suite {
+        name: "elias-fano-random",
+        description: "Elias-Fano access at uniformly random indices",
+        runners: || elias_fano::ACCESS_RUNNERS.to_vec(),
+        run: |output_dir, settings| {
+            elias_fano::random_access(output_dir, settings).map_err(|e| format!("Could not write the Elias-Fano random access results: {}", e))
+        },
+    },
+    Suite {
+        name: "elias-fano-in-order",
+        description: "Complete in-order iteration over Elias-Fano sequences, per element",
+        runners: || elias_fano::ACCESS_RUNNERS.to_vec(),
+        run: |output_dir, settings| {
+            elias_fano::in_order(output_dir, settings).map_err(|e| format!("Could not write the Elias-Fano iteration results: {}", e))
+        },
+    },
+    Suite {
+        name: "elias-fano-pred-random",
+        description: "Elias-Fano predecessor queries on uniformly random sequences",
+        runners: || elias_fano::PREDECESSOR_RUNNERS.to_vec(),
+        run: |output_dir, settings| {
+            elias_fano::predecessor_uniform(output_dir, settings).map_err(|e| format!("Could not write the Elias-Fano predecessor results: {}", e))
+        },
+    },
+    Suite {
+        name: "elias-fano-pred-adversarial",
+        description: "Elias-Fano predecessor queries on sequences with a cluster of far outliers",
+        runners: || elias_fano::PREDECESSOR_RUNNERS.to_vec(),
+        run: |output_dir, settings| {
+            elias_fano::predecessor_adversarial(output_dir, settings).map_err(|e| format!("Could not write the adversarial Elias-Fano predecessor results: {}", e))
+        },
+    },
+    Suite {
+        name: "wavelet",
+        description: "Access, rank, select, quantile and range count on wavelet matrices of 16-bit symbols",
+        runners: || wavelet::RUNNERS.to_vec(),
+        run: |output_dir, settings| {
+            wavelet::benchmark(output_dir, settings).map_err(|e| format!("Could not write the wavelet results: {}", e))
+        },
+    },
+    Suite {
+        name: "memory",
+        description: "Heap size of all rank/select libraries reporting it",
+        runners: library_names,
+        run: |output_dir, settings| {
+            memory::benchmark(output_dir, settings).map_err(|e| format!("Could not write the memory results: {}", e))
+        },
+    },
+];
@@ -1,351 +1,80 @@
+use crate::benches::input::{chain_index, random_words_with_density};
+use crate::benches::structures::{build_random, measure_construction, RankStructure, LIBRARIES};
 use crate::benchmark::Benchmark;
-use crate::measure::Measurement;
-use crate::runner;
-use bio::data_structures::rank_select::RankSelect as BioRsVec;
-use bitm::{BitAccess, BitVec as BitmBV, Rank, RankSelect101111 as BitmVec};
-use bv::BitVec as BVBitVec;
-use fid::{BitVector as FidVec, FID};
-use indexed_bitvec::IndexedBits as IndexedVec;
-use rand::distributions::Uniform;
-use rand::Rng;
-use rsdict::RsDict;
-use std::hint::black_box;
-use std::ops::Div;
+use crate::measure::AccessPattern;
+use crate::runner::{ChainRunner, Runner};
+use crate::settings::{Settings, DEFAULT_DENSITY};
+use rand::rngs::StdRng;
+use std::io;
+use std::marker::PhantomData;
 use std::path::Path;
-use succinct::{BitRankSupport, BitVecPush, BitVector as SuccinctBV, Rank9 as SuccinctR9Vec};
-use sucds::bit_vectors::darray::DArray as SucDsDVec;
-use sucds::bit_vectors::rank9sel::Rank9Sel as SucDsR9Vec;
-use sucds::bit_vectors::{BitVector as SucBitVec, Rank as SucdsR9Rank};
-use sux::prelude::{Block32Counters, Rank9 as SuxR9Vec, RankSmall as SuxSmallVec, RankZero};
-use sux::rank_small;
-use vers_vecs::{BitVec, RsVec};
-use BitVecState::*;
 
-pub(crate) enum BitVecState {
-    Vers(RsVec),
-    RsD(RsDict),
-    Bio(BioRsVec),
-    Fid(FidVec),
-    IndexedBV(IndexedVec<Vec<u8>>),
-    SuccinctR9(SuccinctR9Vec<SuccinctBV<u64>>),
-    SucDsR9(SucDsR9Vec),
-    SucDsDA(SucDsDVec),
-    Bitm(BitmVec),
-    SuxR9(SuxR9Vec),
-    SuxSmall(SuxSmallVec<1, 10, sux::bits::BitVec, Box<[usize]>, Box<[Block32Counters<1, 10>]>>),
+/// Benchmarks `rank0` at the positions chosen by the access pattern on any [`RankStructure`],
+/// built from a random bit vector with the given density of ones.
+pub(crate) struct RankRunner<S> {
+    density: f64,
+    structure: PhantomData<fn() -> S>,
 }
 
-fn create_u64_params(number: usize, len: usize) -> Box<[u64]> {
-    let mut rng = rand::thread_rng();
-    let mut vec = Vec::with_capacity(number);
-    for _ in 0..number {
-        vec.push(rng.gen_range(0..len as u64));
+impl<S> RankRunner<S> {
+    pub(crate) fn new(density: f64) -> Self {
+        Self { density, structure: PhantomData }
     }
-    vec.into_boxed_slice()
 }
 
-runner!(
-    VersRunner,
-    create_context = |size| {
-        let mut bitvec = BitVec::with_capacity(size);
-        let sample = Uniform::new(0, u64::MAX);
-        let full_limbs = size.div(64);
-        let mut rng = rand::thread_rng();
-        for _ in 0..full_limbs {
-            bitvec.append_word(rng.sample(sample));
-        }
+impl<S: RankStructure> Runner for RankRunner<S> {
+    type Context = S;
 
-        Vers(bitvec.into())
-    },
-    prepare_params = |number, len| {
-        create_u64_params(number, len)
-    },
-    execute = |bv: BitVecState, idx: u64| {
-        if let Vers(bv) = bv {
-            black_box(bv.rank0(*idx as usize));
-        } else {
-            panic!("Invalid state");
-        }
-    }
-);
+    type Param = usize;
 
-runner!(
-    RsDictRunner,
-    create_context = |size| {
-        let mut rng = rand::thread_rng();
-        let mut rs_dict = RsDict::with_capacity(size);
-        for _ in 0..size {
-            rs_dict.push(rng.gen_bool(0.5));
-        }
-        RsD(rs_dict)
-    },
-    prepare_params = |number, len| {
-        create_u64_params(number, len)
-    },
-    execute = |bv: BitVecState, idx: u64| {
-        if let RsD(bv) = bv {
-            black_box(bv.rank(*idx, false));
-        } else {
-            panic!("Invalid state");
-        }
-    }
-);
+    type Output = usize;
 
-runner!(
-    BioRunner,
-    create_context = |size| {
-        let mut rng = rand::thread_rng();
-        let mut bio_vec = BVBitVec::new_fill(false, size as u64);
-        for i in 0..size {
-            bio_vec.set(i as u64, rng.gen_bool(0.5));
-        }
-        // k chosen to be a fair comparison to vers. We can also adapt it with growing size
-        // as it is intended, but the library is so slow, it doesn't matter in any case.
-        Bio(BioRsVec::new(bio_vec, 512 / 32))
-    },
-    prepare_params = |number, len| {
-        create_u64_params(number, len)
-    },
-    execute = |bv: BitVecState, idx: u64| {
-        if let Bio(bv) = bv {
-            black_box(bv.rank_0(*idx));
-        } else {
-            panic!("Invalid state");
-        }
+    fn create_context(&self, size: usize, rng: &mut StdRng) -> Self::Context {
+        build_random(size, self.density, rng)
     }
-);
 
-runner!(
-    FidRunner,
-    create_context = |size| {
-        let mut rng = rand::thread_rng();
-        let mut fid_vec = FidVec::new();
-        for _ in 0..size {
-            fid_vec.push(rng.gen_bool(0.5));
-        }
-        Fid(fid_vec)
-    },
-    prepare_params = |number, len| {
-        create_u64_params(number, len)
-    },
-    execute = |bv: BitVecState, idx: u64| {
-        if let Fid(bv) = bv {
-            black_box(bv.rank0(*idx));
-        } else {
-            panic!("Invalid state");
-        }
+    fn density(&self) -> Option<f64> {
+        Some(self.density)
     }
-);
 
-runner!(
-    IndexedBitVecRunner,
-    create_context = |size| {
-        let rng = rand::thread_rng();
-        let sample = Uniform::new(0, u8::MAX);
-        let vec = rng.sample_iter(sample).take(size / 8).collect::<Vec<u8>>();
-        IndexedBV(IndexedVec::build_from_bytes(vec, size as u64).unwrap())
-    },
-    prepare_params = |number, len| {
-        create_u64_params(number, len)
-    },
-    execute = |bv: BitVecState, idx: u64| {
-        if let IndexedBV(bv) = bv {
-            black_box(bv.rank_zeros(*idx));
-        } else {
-            panic!("Invalid state");
-        }
+    fn measure_construction(&self, size: usize, repetitions: usize, rng: &mut StdRng) -> u64 {
+        measure_construction::<S>(repetitions, || random_words_with_density(size, self.density, rng))
     }
-);
 
-runner!(
-    SuccinctR9Runner,
-    create_context = |size| {
-        let mut rng = rand::thread_rng();
-        let mut bit_vec = SuccinctBV::with_capacity(size as u64);
-        for _ in 0..size {
-            bit_vec.push_bit(rng.gen_bool(0.5))
-        }
-        SuccinctR9(SuccinctR9Vec::new(bit_vec))
-    },
-    prepare_params = |number, len| {
-        create_u64_params(number, len)
-    },
-    execute = |bv: BitVecState, idx: u64| {
-        if let SuccinctR9(bv
-        )
-        = bv {
-            black_box(bv.rank0(*idx));
-        }
+    fn prepare_params(&self, number: usize, size: usize, pattern: AccessPattern, rng: &mut StdRng) -> Box<[Self::Param]> {
+        pattern.positions(number, size, rng)
     }
-);
-
-runner!(
-    SucDsR9Runner,
-    create_context = |size| {
-        let mut rng = rand::thread_rng();
-        let sample = Uniform::new(0, u64::MAX);
-        let mut suc_bv = SucBitVec::with_capacity(size);
-        for _ in 0..size.div(64) {
-            suc_bv
-                .push_bits(rng.sample(sample) as usize, 64)
-                .expect("Failed to push bits into sucds bitvector");
-        }
 
-        SucDsR9(SucDsR9Vec::new(suc_bv))
-    },
-    prepare_params = |number, len| {
-        create_u64_params(number, len)
-    },
-    execute = |bv: BitVecState, idx: u64| {
-        if let SucDsR9(bv) = bv {
-            black_box(bv.rank0(*idx as usize));
-        } else {
-            panic!("Invalid state");
-        }
+    #[inline]
+    fn execute(&self, bv: &Self::Context, idx: &Self::Param) -> Self::Output {
+        bv.rank0(*idx)
     }
-);
 
-runner!(
-    SucDsDARunner,
-    create_context = |size| {
-        let mut rng = rand::thread_rng();
-        let sample = Uniform::new(0, u64::MAX);
-        let mut suc_bv = SucBitVec::with_capacity(size);
-        for _ in 0..size.div(64) {
-            suc_bv
-                .push_bits(rng.sample(sample) as usize, 64)
-                .expect("Failed to push bits into sucds bitvector");
-        }
-
-        SucDsDA(SucDsDVec::from_bits(suc_bv.iter()).enable_rank())
-    },
-    prepare_params = |number, len| {
-        create_u64_params(number, len)
-    },
-    execute = |bv: BitVecState, idx: u64| {
-        if let SucDsDA(bv) = bv {
-            black_box(bv.rank0(*idx as usize));
-        } else {
-            panic!("Invalid state");
-        }
+    fn chain_runner(&self) -> Option<&dyn ChainRunner<Context = Self::Context, Param = Self::Param, Output = Self::Output>> {
+        Some(self)
     }
-);
-
-runner!(
-    BitmRunner,
-    create_context = |size| {
-        let mut rng = rand::thread_rng();
-        let mut bv = Box::<[u64]>::with_zeroed_bits(size);
-        for i in 0..size {
-            if rng.gen_bool(0.5) {
-                bv.set_bit(i);
-            }
-        }
-
-        Bitm(bv.into())
-    },
-    prepare_params = |number, len| {
-        create_u64_params(number, len)
-    },
-    execute = |bv: BitVecState, idx: u64| {
-        if let Bitm(bv) = bv {
-            black_box(bv.rank0(*idx as usize));
-        }
-    }
-);
-
-runner!(
-    SuxR9Runner,
-    create_context = |size| {
-        let mut rng = rand::thread_rng();
-        let mut bit_vec = sux::prelude::BitVec::new(size);
-        for i in 0..size {
-            if rng.gen_bool(0.5) {
-                bit_vec.set(i, true)
-            }
-        }
+}
 
-        SuxR9(SuxR9Vec::new(bit_vec))
-    },
-    prepare_params = |number, len| {
-        create_u64_params(number, len)
-    },
-    execute = |bv: BitVecState, idx: u64| {
-        if let SuxR9(bv) = bv {
-            black_box(bv.rank_zero(*idx as usize));
-        } else {
-            panic!("Invalid state");
-        }
+impl<S: RankStructure> ChainRunner for RankRunner<S> {
+    #[inline]
+    fn chain(&self, _bv: &Self::Context, result: Self::Output, step: usize, len: usize) -> Self::Param {
+        chain_index(result, step, len)
     }
-);
+}
 
-runner!(
-    SuxSmallRunner,
-    create_context = |size| {
-        let mut rng = rand::thread_rng();
-        let mut bit_vec = sux::prelude::BitVec::new(size);
-        for i in 0..size {
-            if rng.gen_bool(0.5) {
-                bit_vec.set(i, true)
-            }
-        }
-    
-        SuxSmall(rank_small![2; bit_vec])
-    },
-    prepare_params = |number, len| {
-        create_u64_params(number, len)
-    },
-    execute = |bv: BitVecState, idx: u64| {
-        if let SuxSmall(bv) = bv {
-            black_box(bv.rank_zero(*idx as usize));
-        } else {
-            panic!("Invalid state");
-        }
+pub(crate) fn benchmark(output_dir: &Path, settings: &Settings) -> io::Result<()> {
+    // all densities are measured interleaved, as separate runners of the same benchmark
+    let runners = settings
+        .densities(DEFAULT_DENSITY)
+        .into_iter()
+        .flat_map(|density| LIBRARIES.iter().map(move |library| library.rank(density)))
+        .collect::<Vec<_>>();
+
+    // every power of two, restricted to the configured size range by the benchmark
+    let sizes = (7..=32).map(|log_size| 1 << log_size).collect();
+    let mut benchmark = Benchmark::new("Rank", sizes).with_settings(settings);
+    for runner in &runners {
+        runner.register(&mut benchmark);
     }
-);
-
-pub(crate) fn benchmark(output_dir: &Path) {
-    let mut benchmark = Benchmark::<BitVecState, u64>::new(
-        "Rank",
-        vec![
-            1 << 7,
-            1 << 8,
-            1 << 9,
-            1 << 10,
-            1 << 11,
-            1 << 12,
-            1 << 13,
-            1 << 14,
-            1 << 15,
-            1 << 16,
-            1 << 17,
-            1 << 18,
-            1 << 19,
-            1 << 20,
-            1 << 21,
-            1 << 22,
-            1 << 23,
-            1 << 24,
-            1 << 25,
-            1 << 26,
-            1 << 27,
-            1 << 28,
-            1 << 29,
-            1 << 30,
-            1 << 31,
-            1 << 32,
-        ],
-    );
-    benchmark.add_measurement(Measurement::new("Vers", &VersRunner));
-    benchmark.add_measurement(Measurement::new("RsDict", &RsDictRunner));
-    benchmark.add_measurement(Measurement::new("Bio", &BioRunner));
-    benchmark.add_measurement(Measurement::new("FID", &FidRunner));
-    benchmark.add_measurement(Measurement::new("IBV", &IndexedBitVecRunner));
-    benchmark.add_measurement(Measurement::new("SctR9", &SuccinctR9Runner));
-    benchmark.add_measurement(Measurement::new("SDSR9", &SucDsR9Runner));
-    benchmark.add_measurement(Measurement::new("SDSDA", &SucDsDARunner));
-    benchmark.add_measurement(Measurement::new("Bitm", &BitmRunner));
-    benchmark.add_measurement(Measurement::new("SuxR9", &SuxR9Runner));
-    benchmark.add_measurement(Measurement::new("SuxSmall", &SuxSmallRunner));
-    benchmark.benchmark(output_dir);
+    benchmark.benchmark(output_dir)
 }
@@ -0,0 +1,141 @@
+use crate::benches::input::{random_values, reduce, scramble};
+use crate::benchmark::Benchmark;
+use crate::measure::{AccessPattern, Measurement};
+use crate::runner;
+use crate::runner::IntoOutput;
+use crate::settings::Settings;
+use librualg::segment_tree::RmqMin;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::io;
+use std::path::Path;
+use vers_vecs::{BinaryRmq, FastRmq};
+
+/// The names of all runners of the RMQ suite.
+pub(crate) const RUNNERS: [&str; 4] = ["Vers Fast", "Vers Binary", "Librualg", "RMQ Crate"];
+
+/// The sparse table of `BinaryRmq` needs `n log n` words, which exceeds the memory of typical
+/// machines from this size on.
+const BINARY_RMQ_SIZE_LIMIT: usize = 1 << 26;
+
+/// Generate `number` inclusive query ranges `(left, right)` with `left <= right`. One end of each range
+/// follows the access pattern, and the other one is uniformly random.
+fn create_ranges(number: usize, len: usize, pattern: AccessPattern, rng: &mut StdRng) -> Box<[(usize, usize)]> {
+    let positions = pattern.positions(number, len, rng);
+    let mut vec = Vec::with_capacity(number);
+    for &a in positions.iter() {
+        let b = rng.gen_range(0..len);
+        vec.push((a.min(b), a.max(b)));
+    }
+    vec.into_boxed_slice()
+}
+
+/// Derive the next query range of a dependent chain from the position of the previous minimum.
+fn chain_range(result: usize, step: usize, len: usize) -> (usize, usize) {
+    let a = scramble(result as u64 ^ step as u64);
+    let b = scramble(a);
+    let (a, b) = (reduce(a, len as u64) as usize, reduce(b, len as u64) as usize);
+    (a.min(b), a.max(b))
+}
+
+runner!(
+    FastRmqRunner,
+    generate_input = |size, rng| {
+        random_values(size, rng)
+    },
+    build = |values: Vec<u64>| {
+        FastRmq::from_vec(values)
+    },
+    prepare_params = |number, len, pattern, rng| {
+        create_ranges(number, len, pattern, rng)
+    },
+    execute = |rmq: FastRmq, range: (usize, usize)| -> usize {
+        rmq.range_min(range.0, range.1).into_output()
+    },
+    chain = |result, step, len| {
+        chain_range(result, step, len)
+    }
+);
+
+runner!(
+    BinaryRmqRunner,
+    generate_input = |size, rng| {
+        random_values(size, rng)
+    },
+    build = |values: Vec<u64>| {
+        BinaryRmq::from_vec(values)
+    },
+    prepare_params = |number, len, pattern, rng| {
+        create_ranges(number, len, pattern, rng)
+    },
+    execute = |rmq: BinaryRmq, range: (usize, usize)| -> usize {
+        rmq.range_min(range.0, range.1).into_output()
+    },
+    chain = |result, step, len| {
+        chain_range(result, step, len)
+    },
+    size_limit = BINARY_RMQ_SIZE_LIMIT
+);
+
+runner!(
+    LibrualgRunner,
+    generate_input = |size, rng| {
+        random_values(size, rng).into_iter().map(|value| value as usize).collect::<Vec<_>>()
+    },
+    build = |values: Vec<usize>| {
+        RmqMin::new(&values)
+    },
+    prepare_params = |number, len, pattern, rng| {
+        create_ranges(number, len, pattern, rng)
+    },
+    execute = |rmq: RmqMin<usize>, range: (usize, usize)| -> usize {
+        rmq.query(range.0, range.1).into_output()
+    },
+    chain = |result, step, len| {
+        chain_range(result, step, len)
+    }
+);
+
+runner!(
+    RmqCrateRunner,
+    generate_input = |size, rng| {
+        random_values(size, rng)
+    },
+    build = |values: Vec<u64>| {
+        range_minimum_query::Rmq::from_iter(values)
+    },
+    prepare_params = |number, len, pattern, rng| {
+        create_ranges(number, len, pattern, rng)
+    },
+    execute = |rmq: range_minimum_query::Rmq, range: (usize, usize)| -> usize {
+        rmq.range_minimum(range.0..=range.1).into_output()
+    },
+    chain = |result, step, len| {
+        chain_range(result, step, len)
+    }
+);
+
+pub(crate) fn benchmark(output_dir: &Path, settings: &Settings) -> io::Result<()> {
+    let mut benchmark = Benchmark::new(
+        "RMQ",
+        vec![
+            1 << 8,
+            1 << 10,
+            1 << 12,
+            1 << 14,
+            1 << 16,
+            1 << 18,
+            1 << 20,
+            1 << 22,
+            1 << 24,
+            1 << 26,
+            1 << 28,
+        ],
+    )
+    .with_settings(settings);
+    benchmark.add_measurement(Measurement::new(RUNNERS[0], &FastRmqRunner));
+    benchmark.add_measurement(Measurement::new(RUNNERS[1], &BinaryRmqRunner));
+    benchmark.add_measurement(Measurement::new(RUNNERS[2], &LibrualgRunner));
+    benchmark.add_measurement(Measurement::new(RUNNERS[3], &RmqCrateRunner));
+    benchmark.benchmark(output_dir)
+}
@@ -0,0 +1,104 @@
+use crate::benches::adversarial::{Pattern, PATTERNS};
+use crate::benches::input::chain_index;
+use crate::benches::structures::{measure_construction, RankSelectStructure, LIBRARIES};
+use crate::benchmark::Benchmark;
+use crate::measure::AccessPattern;
+use crate::runner::{ChainRunner, Runner};
+use crate::settings::Settings;
+use rand::rngs::StdRng;
+use std::io;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::Path;
+
+/// Benchmarks `select1`, or `select0` for patterns of isolated zeros, on a worst-case [`Pattern`] with
+/// any [`RankSelectStructure`]. The patterns are deterministic, so the input rng is not used.
+pub(crate) struct AdversarialSelectRunner<S> {
+    pattern: Pattern,
+    density: f64,
+    structure: PhantomData<fn() -> S>,
+}
+
+impl<S> AdversarialSelectRunner<S> {
+    pub(crate) fn new(pattern: Pattern, density: f64) -> Self {
+        Self { pattern, density, structure: PhantomData }
+    }
+}
+
+/// A structure built from a pattern together with the ranks queried on it, see
+/// [`Pattern::query_ranks`], so dependent chains do not compute them on every step.
+pub(crate) struct AdversarialContext<S> {
+    structure: S,
+    ranks: Range<usize>,
+}
+
+impl<S: RankSelectStructure> Runner for AdversarialSelectRunner<S> {
+    type Context = AdversarialContext<S>;
+
+    type Param = usize;
+
+    type Output = usize;
+
+    fn create_context(&self, size: usize, _rng: &mut StdRng) -> Self::Context {
+        let structure = S::from_words(self.pattern.words(size, self.density).into_iter());
+        AdversarialContext { structure, ranks: self.pattern.query_ranks(size, self.density) }
+    }
+
+    fn density(&self) -> Option<f64> {
+        Some(self.density)
+    }
+
+    fn measure_construction(&self, size: usize, repetitions: usize, _rng: &mut StdRng) -> u64 {
+        measure_construction::<S>(repetitions, || self.pattern.words(size, self.density))
+    }
+
+    fn prepare_params(&self, number: usize, size: usize, pattern: AccessPattern, rng: &mut StdRng) -> Box<[Self::Param]> {
+        let ranks = self.pattern.query_ranks(size, self.density);
+        pattern.positions(number, ranks.len(), rng).iter().map(|&rank| ranks.start + rank).collect()
+    }
+
+    #[inline]
+    fn execute(&self, context: &Self::Context, rank: &Self::Param) -> Self::Output {
+        if self.pattern.selects_zeros() {
+            context.structure.select0(*rank)
+        } else {
+            context.structure.select1(*rank)
+        }
+    }
+
+    fn chain_runner(&self) -> Option<&dyn ChainRunner<Context = Self::Context, Param = Self::Param, Output = Self::Output>> {
+        Some(self)
+    }
+}
+
+impl<S: RankSelectStructure> ChainRunner for AdversarialSelectRunner<S> {
+    #[inline]
+    fn chain(&self, context: &Self::Context, result: Self::Output, step: usize, _len: usize) -> Self::Param {
+        context.ranks.start + chain_index(result, step, context.ranks.len())
+    }
+}
+
+/// Benchmark the select queries of all libraries on every worst-case pattern, each as its own
+/// benchmark with its own result file. The patterns are generated with the configured densities, or
+/// with their own default density if none are configured.
+pub(crate) fn benchmark(output_dir: &Path, settings: &Settings) -> io::Result<()> {
+    for pattern in PATTERNS {
+        // all densities are measured interleaved, as separate runners of the same benchmark
+        let runners = settings
+            .densities(pattern.default_density())
+            .into_iter()
+            .flat_map(|density| LIBRARIES.iter().map(move |library| library.adversarial_select(pattern, density)))
+            .collect::<Vec<_>>();
+
+        let mut benchmark = Benchmark::new(
+            &format!("Select_Adversarial_{}", pattern.name()),
+            vec![1 << 14, 1 << 16, 1 << 18, 1 << 20, 1 << 22, 1 << 24, 1 << 26],
+        )
+        .with_settings(settings);
+        for runner in &runners {
+            runner.register(&mut benchmark);
+        }
+        benchmark.benchmark(output_dir)?;
+    }
+    Ok(())
+}
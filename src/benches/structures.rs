@@ -0,0 +1,755 @@
+//! Adapters for all rank/select libraries we compare against. Each adapter implements
+//! [`RankSelectStructure`] and is registered in [`LIBRARIES`], which the rank, select, memory and
+//! correctness suites iterate over. Adding a library only requires a new adapter in this file.
+
+use crate::benches::adversarial::Pattern;
+use crate::benches::input::{bits, random_words_with_density};
+use crate::benches::rank::RankRunner;
+use crate::benches::select::SelectRunner;
+use crate::benches::select_adversarial::AdversarialSelectRunner;
+use crate::benchmark::Benchmark;
+use crate::measure::Measurement;
+use crate::runner::{IntoOutput, Runner};
+use bio::data_structures::rank_select::RankSelect as BioRsVec;
+use bitm::{CombinedSampling, Rank, RankSelect101111 as BitmVec, Select, Select0};
+use bv::BitVec as BVBitVec;
+use fid::{BitVector as FidVec, FID};
+use indexed_bitvec::IndexedBits as IndexedVec;
+use rand::rngs::StdRng;
+use rsdict::RsDict;
+use std::marker::PhantomData;
+use std::time::Instant;
+use succinct::select::BinSearchSelect;
+use succinct::{BitRankSupport, BitVecPush, BitVector as SuccinctBV, Rank9 as SuccinctR9Vec, Select0Support, Select1Support, SpaceUsage};
+use sucds::bit_vectors::darray::DArray as SucDsDVec;
+use sucds::bit_vectors::rank9sel::Rank9Sel as SucDsR9Vec;
+use sucds::bit_vectors::{BitVector as SucBitVec, Rank as SucdsRank, Select as SucdsSelect};
+use sucds::Serializable;
+use sux::prelude::{BitVec as SuxVec, Block32Counters, Rank as SuxRank, Rank9 as SuxR9Vec, RankSmall as SuxSmallVec, RankZero, Select as SuxSelect, SelectAdapt, SelectZero, SelectZeroAdapt};
+use sux::rank_small;
+use vers_vecs::{BitVec, RsVec};
+
+/// A bit vector with rank support.
+/// Ranks count the bits strictly before a position, so every adapter answers the same query with
+/// the same result.
+pub(crate) trait RankStructure: Sized {
+    /// The name of the library used in the measurement output.
+    const NAME: &'static str;
+
+    /// Build the structure from the words of a bit vector, least significant bit first.
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self;
+
+    fn rank0(&self, pos: usize) -> usize;
+
+    fn rank1(&self, pos: usize) -> usize;
+
+    /// The heap size of the structure in bytes, if the library reports it.
+    fn heap_size(&self) -> Option<usize>;
+}
+
+/// A bit vector with rank and select support.
+/// Selects take 0-based ranks. Libraries that build separate select indices implement this on a
+/// different adapter than [`RankStructure`], so the rank suite does not pay for them.
+pub(crate) trait RankSelectStructure: RankStructure {
+    fn select0(&self, rank: usize) -> usize;
+
+    fn select1(&self, rank: usize) -> usize;
+}
+
+/// Build a structure from a random bit vector with `size` bits and the given density of ones,
+/// generated by `rng`.
+pub(crate) fn build_random<S: RankStructure>(size: usize, density: f64, rng: &mut StdRng) -> S {
+    S::from_words(random_words_with_density(size, density, rng).into_iter())
+}
+
+/// Build `repetitions` structures from the words of the bit vectors generated by `input`, and return
+/// the nanoseconds spent building them. Generating the bit vectors and dropping the structures is
+/// not timed.
+pub(crate) fn measure_construction<S: RankStructure>(repetitions: usize, input: impl FnMut() -> Vec<u64>) -> u64 {
+    let inputs = std::iter::repeat_with(input).take(repetitions).collect::<Vec<_>>();
+    let mut structures = Vec::with_capacity(repetitions);
+
+    let start = Instant::now();
+    for words in inputs {
+        structures.push(S::from_words(words.into_iter()));
+    }
+    let elapsed = start.elapsed().as_nanos() as u64;
+
+    drop(structures);
+    elapsed
+}
+
+/// Object-safe handle to a [`RankSelectStructure`] adapter, used to register the same library in
+/// every suite.
+pub(crate) trait Library: Sync {
+    fn name(&self) -> &'static str;
+
+    /// Create a runner of this library's rank queries on random bit vectors with the given density.
+    fn rank(&self, density: f64) -> Box<dyn OwnedRunner>;
+
+    /// Create a runner of this library's select queries on random bit vectors with the given density.
+    fn select(&self, density: f64) -> Box<dyn OwnedRunner>;
+
+    /// Create a runner of this library's select queries on a worst-case pattern.
+    fn adversarial_select(&self, pattern: Pattern, density: f64) -> Box<dyn OwnedRunner>;
+
+    /// Build the structure with rank support only from the given words and return its heap size, if
+    /// the library reports it.
+    fn rank_heap_size(&self, words: &[u64]) -> Option<usize>;
+
+    /// Build the structure with rank and select support from the given words and return its heap
+    /// size, if the library reports it.
+    fn select_heap_size(&self, words: &[u64]) -> Option<usize>;
+
+    /// Build both variants of the structure from the given words and compare their answers for the
+    /// given positions and ranks against `expected`. Returns a description of every mismatch.
+    fn verify(&self, words: &[u64], expected: &[Expected]) -> Vec<String>;
+}
+
+/// A runner configured at run time, together with the name it is measured under. Unlike the
+/// constant runners, it cannot be borrowed for the whole program, so the suite keeps it alive while a
+/// benchmark borrows it.
+pub(crate) trait OwnedRunner {
+    fn register<'a>(&'a self, benchmark: &mut Benchmark<'a>);
+}
+
+struct Named<R> {
+    name: &'static str,
+    runner: R,
+}
+
+impl<R: Runner> OwnedRunner for Named<R> {
+    fn register<'a>(&'a self, benchmark: &mut Benchmark<'a>) {
+        benchmark.add_measurement(Measurement::new(self.name, &self.runner));
+    }
+}
+
+/// Reference answers for one position of a bit vector, computed without any library.
+pub(crate) struct Expected {
+    pub(crate) pos: usize,
+    pub(crate) rank0: usize,
+    pub(crate) rank1: usize,
+    /// The position of the zero with rank `pos`, if there are enough zeros.
+    pub(crate) select0: Option<usize>,
+    /// The position of the one with rank `pos`, if there are enough ones.
+    pub(crate) select1: Option<usize>,
+}
+
+/// The adapters of one library: `R` is built by the rank suite, `S` by the select suites.
+struct Entry<R, S>(PhantomData<fn() -> (R, S)>);
+
+impl<R: RankStructure + 'static, S: RankSelectStructure + 'static> Library for Entry<R, S> {
+    fn name(&self) -> &'static str {
+        S::NAME
+    }
+
+    fn rank(&self, density: f64) -> Box<dyn OwnedRunner> {
+        Box::new(Named { name: S::NAME, runner: RankRunner::<R>::new(density) })
+    }
+
+    fn select(&self, density: f64) -> Box<dyn OwnedRunner> {
+        Box::new(Named { name: S::NAME, runner: SelectRunner::<S>::new(density) })
+    }
+
+    fn adversarial_select(&self, pattern: Pattern, density: f64) -> Box<dyn OwnedRunner> {
+        Box::new(Named { name: S::NAME, runner: AdversarialSelectRunner::<S>::new(pattern, density) })
+    }
+
+    fn rank_heap_size(&self, words: &[u64]) -> Option<usize> {
+        R::from_words(words.iter().copied()).heap_size()
+    }
+
+    fn select_heap_size(&self, words: &[u64]) -> Option<usize> {
+        S::from_words(words.iter().copied()).heap_size()
+    }
+
+    fn verify(&self, words: &[u64], expected: &[Expected]) -> Vec<String> {
+        let rank_structure = R::from_words(words.iter().copied());
+        let structure = S::from_words(words.iter().copied());
+        let mut mismatches = Vec::new();
+        let mut check = |query: &str, arg: usize, actual: usize, expected: usize| {
+            if actual != expected {
+                mismatches.push(format!("{}: {query}({arg}) returned {actual}, expected {expected}", S::NAME));
+            }
+        };
+
+        for e in expected {
+            check("rank0", e.pos, rank_structure.rank0(e.pos), e.rank0);
+            check("rank1", e.pos, rank_structure.rank1(e.pos), e.rank1);
+            check("rank0", e.pos, structure.rank0(e.pos), e.rank0);
+            check("rank1", e.pos, structure.rank1(e.pos), e.rank1);
+            if let Some(select0) = e.select0 {
+                check("select0", e.pos, structure.select0(e.pos), select0);
+            }
+            if let Some(select1) = e.select1 {
+                check("select1", e.pos, structure.select1(e.pos), select1);
+            }
+        }
+        mismatches
+    }
+}
+
+/// All libraries that are benchmarked by the rank/select suites.
+pub(crate) static LIBRARIES: &[&dyn Library] = &[
+    &Entry::<Vers, Vers>(PhantomData),
+    &Entry::<RsD, RsD>(PhantomData),
+    &Entry::<Bio, Bio>(PhantomData),
+    &Entry::<Fid, Fid>(PhantomData),
+    &Entry::<IndexedBV, IndexedBV>(PhantomData),
+    &Entry::<SuccinctR9, SuccinctR9>(PhantomData),
+    &Entry::<SucDsR9Rank, SucDsR9>(PhantomData),
+    &Entry::<SucDsDARank, SucDsDA>(PhantomData),
+    &Entry::<BitmRank, Bitm>(PhantomData),
+    &Entry::<SuxR9Rank, SuxR9>(PhantomData),
+    &Entry::<SuxSmallRank, SuxSmall>(PhantomData),
+];
+
+/// The names of all libraries in [`LIBRARIES`].
+pub(crate) fn library_names() -> Vec<&'static str> {
+    LIBRARIES.iter().map(|library| library.name()).collect()
+}
+
+fn sucds_bit_vec(words: impl ExactSizeIterator<Item = u64>) -> SucBitVec {
+    let mut suc_bv = SucBitVec::with_capacity(words.len() * 64);
+    for word in words {
+        suc_bv
+            .push_bits(word as usize, 64)
+            .expect("Failed to push bits into sucds bitvector");
+    }
+    suc_bv
+}
+
+fn sux_bit_vec(words: impl ExactSizeIterator<Item = u64>) -> SuxVec {
+    let words = words.collect::<Vec<_>>();
+    let mut bit_vec = SuxVec::new(words.len() * 64);
+    for (i, bit) in bits(&words).enumerate() {
+        if bit {
+            bit_vec.set(i, true)
+        }
+    }
+    bit_vec
+}
+
+pub(crate) struct Vers(RsVec);
+
+impl RankStructure for Vers {
+    const NAME: &'static str = "Vers";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        let mut bitvec = BitVec::with_capacity(words.len() * 64);
+        for word in words {
+            bitvec.append_word(word);
+        }
+        Vers(bitvec.into())
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank0(pos)
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank1(pos)
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        Some(self.0.heap_size())
+    }
+}
+
+impl RankSelectStructure for Vers {
+    fn select0(&self, rank: usize) -> usize {
+        self.0.select0(rank)
+    }
+
+    fn select1(&self, rank: usize) -> usize {
+        self.0.select1(rank)
+    }
+}
+
+pub(crate) struct RsD(RsDict);
+
+impl RankStructure for RsD {
+    const NAME: &'static str = "RsDict";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        let words = words.collect::<Vec<_>>();
+        let mut rs_dict = RsDict::with_capacity(words.len() * 64);
+        for bit in bits(&words) {
+            rs_dict.push(bit);
+        }
+        RsD(rs_dict)
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank(pos as u64, false).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank(pos as u64, true).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        Some(self.0.heap_size())
+    }
+}
+
+impl RankSelectStructure for RsD {
+    fn select0(&self, rank: usize) -> usize {
+        self.0.select0(rank as u64).into_output()
+    }
+
+    fn select1(&self, rank: usize) -> usize {
+        self.0.select1(rank as u64).into_output()
+    }
+}
+
+/// Bio counts ranks inclusively and selects with 1-based ranks, which the adapter translates.
+pub(crate) struct Bio(BioRsVec);
+
+impl RankStructure for Bio {
+    const NAME: &'static str = "Bio";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        let words = words.collect::<Vec<_>>();
+        let mut bio_vec = BVBitVec::new_fill(false, words.len() as u64 * 64);
+        for (i, bit) in bits(&words).enumerate() {
+            bio_vec.set(i as u64, bit);
+        }
+        // k chosen to be a fair comparison to vers. We can also adapt it with growing size
+        // as it is intended, but the library is so slow, it doesn't matter in any case.
+        Bio(BioRsVec::new(bio_vec, 512 / 32))
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        if pos == 0 {
+            0
+        } else {
+            self.0.rank_0(pos as u64 - 1).into_output()
+        }
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        if pos == 0 {
+            0
+        } else {
+            self.0.rank_1(pos as u64 - 1).into_output()
+        }
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl RankSelectStructure for Bio {
+    fn select0(&self, rank: usize) -> usize {
+        self.0.select_0(rank as u64 + 1).into_output()
+    }
+
+    fn select1(&self, rank: usize) -> usize {
+        self.0.select_1(rank as u64 + 1).into_output()
+    }
+}
+
+pub(crate) struct Fid(FidVec);
+
+impl RankStructure for Fid {
+    const NAME: &'static str = "FID";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        let words = words.collect::<Vec<_>>();
+        let mut fid_vec = FidVec::new();
+        for bit in bits(&words) {
+            fid_vec.push(bit);
+        }
+        Fid(fid_vec)
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank0(pos as u64).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank1(pos as u64).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl RankSelectStructure for Fid {
+    fn select0(&self, rank: usize) -> usize {
+        self.0.select0(rank as u64).into_output()
+    }
+
+    fn select1(&self, rank: usize) -> usize {
+        self.0.select1(rank as u64).into_output()
+    }
+}
+
+pub(crate) struct IndexedBV(IndexedVec<Vec<u8>>);
+
+impl RankStructure for IndexedBV {
+    const NAME: &'static str = "IBV";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        let size = words.len() as u64 * 64;
+        // indexed_bitvec stores the most significant bit of each byte first
+        let bytes = words
+            .flat_map(|word| word.to_le_bytes())
+            .map(u8::reverse_bits)
+            .collect::<Vec<u8>>();
+        IndexedBV(IndexedVec::build_from_bytes(bytes, size).unwrap())
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank_zeros(pos as u64).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank_ones(pos as u64).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl RankSelectStructure for IndexedBV {
+    fn select0(&self, rank: usize) -> usize {
+        self.0.select_zeros(rank as u64).into_output()
+    }
+
+    fn select1(&self, rank: usize) -> usize {
+        self.0.select_ones(rank as u64).into_output()
+    }
+}
+
+/// Succinct's rank9 has no select support of its own, so selects are answered by binary search over
+/// the ranks. Succinct counts ranks inclusively, which the adapter translates.
+pub(crate) struct SuccinctR9(BinSearchSelect<SuccinctR9Vec<SuccinctBV<u64>>>);
+
+impl RankStructure for SuccinctR9 {
+    const NAME: &'static str = "SctR9";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        let words = words.collect::<Vec<_>>();
+        let mut bit_vec = SuccinctBV::with_capacity(words.len() as u64 * 64);
+        for bit in bits(&words) {
+            bit_vec.push_bit(bit)
+        }
+        SuccinctR9(BinSearchSelect::new(SuccinctR9Vec::new(bit_vec)))
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        if pos == 0 {
+            0
+        } else {
+            self.0.inner().rank0(pos as u64 - 1).into_output()
+        }
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        if pos == 0 {
+            0
+        } else {
+            self.0.inner().rank1(pos as u64 - 1).into_output()
+        }
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        Some(self.0.inner().heap_bytes())
+    }
+}
+
+impl RankSelectStructure for SuccinctR9 {
+    fn select0(&self, rank: usize) -> usize {
+        self.0.select0(rank as u64).into_output()
+    }
+
+    fn select1(&self, rank: usize) -> usize {
+        self.0.select1(rank as u64).into_output()
+    }
+}
+
+/// Sucds's rank9 without the select hints, which only the select suites need.
+pub(crate) struct SucDsR9Rank(SucDsR9Vec);
+
+impl RankStructure for SucDsR9Rank {
+    const NAME: &'static str = "SDSR9";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        SucDsR9Rank(SucDsR9Vec::new(sucds_bit_vec(words)))
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank0(pos).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank1(pos).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        Some(self.0.size_in_bytes())
+    }
+}
+
+pub(crate) struct SucDsR9(SucDsR9Vec);
+
+impl RankStructure for SucDsR9 {
+    const NAME: &'static str = "SDSR9";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        SucDsR9(SucDsR9Vec::new(sucds_bit_vec(words)).select1_hints().select0_hints())
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank0(pos).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank1(pos).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        Some(self.0.size_in_bytes())
+    }
+}
+
+impl RankSelectStructure for SucDsR9 {
+    fn select0(&self, rank: usize) -> usize {
+        self.0.select0(rank).into_output()
+    }
+
+    fn select1(&self, rank: usize) -> usize {
+        self.0.select1(rank).into_output()
+    }
+}
+
+/// The darray with rank support only, without the index for `select0`.
+pub(crate) struct SucDsDARank(SucDsDVec);
+
+impl RankStructure for SucDsDARank {
+    const NAME: &'static str = "SDSDA";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        SucDsDARank(SucDsDVec::from_bits(sucds_bit_vec(words).iter()).enable_rank())
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank0(pos).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank1(pos).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        Some(self.0.size_in_bytes())
+    }
+}
+
+pub(crate) struct SucDsDA(SucDsDVec);
+
+impl RankStructure for SucDsDA {
+    const NAME: &'static str = "SDSDA";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        SucDsDA(SucDsDVec::from_bits(sucds_bit_vec(words).iter()).enable_rank().enable_select0())
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank0(pos).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank1(pos).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        Some(self.0.size_in_bytes())
+    }
+}
+
+impl RankSelectStructure for SucDsDA {
+    fn select0(&self, rank: usize) -> usize {
+        self.0.select0(rank).into_output()
+    }
+
+    fn select1(&self, rank: usize) -> usize {
+        self.0.select1(rank).into_output()
+    }
+}
+
+/// Bitm with its default binary search selects, which build no sampling arrays.
+pub(crate) struct BitmRank(BitmVec);
+
+impl RankStructure for BitmRank {
+    const NAME: &'static str = "Bitm";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        BitmRank(words.collect::<Box<[u64]>>().into())
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank0(pos).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank(pos).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+pub(crate) struct Bitm(BitmVec<CombinedSampling, CombinedSampling>);
+
+impl RankStructure for Bitm {
+    const NAME: &'static str = "Bitm";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        Bitm(words.collect::<Box<[u64]>>().into())
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank0(pos).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank(pos).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl RankSelectStructure for Bitm {
+    fn select0(&self, rank: usize) -> usize {
+        self.0.select0(rank).into_output()
+    }
+
+    fn select1(&self, rank: usize) -> usize {
+        self.0.select(rank).into_output()
+    }
+}
+
+/// Sux's rank9 without the select adapters, which only the select suites need.
+pub(crate) struct SuxR9Rank(SuxR9Vec<SuxVec>);
+
+impl RankStructure for SuxR9Rank {
+    const NAME: &'static str = "SuxR9";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        SuxR9Rank(SuxR9Vec::new(sux_bit_vec(words)))
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank_zero(pos).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank(pos).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+pub(crate) struct SuxR9(SelectZeroAdapt<SelectAdapt<SuxR9Vec<SuxVec>>>);
+
+impl RankStructure for SuxR9 {
+    const NAME: &'static str = "SuxR9";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        let rank_support = SuxR9Vec::new(sux_bit_vec(words));
+        SuxR9(SelectZeroAdapt::new(SelectAdapt::new(rank_support, 4), 4))
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank_zero(pos).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank(pos).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl RankSelectStructure for SuxR9 {
+    fn select0(&self, rank: usize) -> usize {
+        self.0.select_zero(rank).into_output()
+    }
+
+    fn select1(&self, rank: usize) -> usize {
+        self.0.select(rank).into_output()
+    }
+}
+
+/// Sux's small rank without the select adapters, which only the select suites need.
+pub(crate) struct SuxSmallRank(SuxSmallBV);
+
+impl RankStructure for SuxSmallRank {
+    const NAME: &'static str = "SuxSmall";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        let bit_vec = sux_bit_vec(words);
+        SuxSmallRank(rank_small![2; bit_vec])
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank_zero(pos).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank(pos).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+type SuxSmallBV = SuxSmallVec<1, 10, SuxVec, Box<[usize]>, Box<[Block32Counters<1, 10>]>>;
+
+pub(crate) struct SuxSmall(SelectZeroAdapt<SelectAdapt<SuxSmallBV>>);
+
+impl RankStructure for SuxSmall {
+    const NAME: &'static str = "SuxSmall";
+
+    fn from_words(words: impl ExactSizeIterator<Item = u64>) -> Self {
+        let bit_vec = sux_bit_vec(words);
+        let rank_support = rank_small![2; bit_vec];
+        SuxSmall(SelectZeroAdapt::new(SelectAdapt::new(rank_support, 4), 4))
+    }
+
+    fn rank0(&self, pos: usize) -> usize {
+        self.0.rank_zero(pos).into_output()
+    }
+
+    fn rank1(&self, pos: usize) -> usize {
+        self.0.rank(pos).into_output()
+    }
+
+    fn heap_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl RankSelectStructure for SuxSmall {
+    fn select0(&self, rank: usize) -> usize {
+        self.0.select_zero(rank).into_output()
+    }
+
+    fn select1(&self, rank: usize) -> usize {
+        self.0.select(rank).into_output()
+    }
+}
@@ -0,0 +1,332 @@
+use crate::benches::input::{chain_index, random_values, reduce, scramble};
+use crate::benchmark::Benchmark;
+use crate::measure::{AccessPattern, Measurement};
+use crate::runner;
+use crate::runner::IntoOutput;
+use crate::settings::Settings;
+use qwt::{AccessUnsigned, RankUnsigned, SelectUnsigned, QWT512};
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::io;
+use std::path::Path;
+use vers_vecs::{BitVec, WaveletMatrix};
+
+/// The names of all runners of the wavelet suite. Not every library supports every operation.
+pub(crate) const RUNNERS: [&str; 4] = ["Vers", "Cseq", "Wavelet Matrix", "QWT"];
+
+/// The width of the symbols of the sequences. Every 64-bit input word holds four symbols.
+const BITS_PER_SYMBOL: u16 = 16;
+
+const SYMBOLS_PER_WORD: usize = 64 / BITS_PER_SYMBOL as usize;
+
+const ALPHABET_SIZE: u64 = 1 << BITS_PER_SYMBOL;
+
+/// Generate the packed words of a sequence of `size` uniformly random symbols.
+fn random_sequence(size: usize, rng: &mut StdRng) -> Vec<u64> {
+    random_values(size / SYMBOLS_PER_WORD, rng)
+}
+
+/// Iterate over the symbols packed into `words`, least significant symbol first.
+fn symbols(words: &[u64]) -> impl Iterator<Item = u64> + '_ {
+    words
+        .iter()
+        .flat_map(|&word| (0..SYMBOLS_PER_WORD).map(move |i| (word >> (i * BITS_PER_SYMBOL as usize)) & (ALPHABET_SIZE - 1)))
+}
+
+fn build_vers(words: &[u64]) -> WaveletMatrix {
+    let bit_vec = BitVec::pack_sequence_u64(words, 64);
+    WaveletMatrix::from_bit_vec(&bit_vec, BITS_PER_SYMBOL)
+}
+
+fn build_cseq(words: &[u64]) -> cseq::wavelet_matrix::Sequence {
+    cseq::wavelet_matrix::Sequence::from_bits(words, words.len() * SYMBOLS_PER_WORD, BITS_PER_SYMBOL as _)
+}
+
+fn build_wavelet_matrix(words: &[u64]) -> wavelet_matrix::WaveletMatrix {
+    let mut builder = wavelet_matrix::WaveletMatrixBuilder::new();
+    for symbol in symbols(words) {
+        builder.push(symbol);
+    }
+    builder.build()
+}
+
+fn build_qwt(words: &[u64]) -> QWT512<u64> {
+    QWT512::from_iter(symbols(words))
+}
+
+/// A uniformly random symbol derived from a query result, for dependent chains.
+fn chain_symbol(result: usize, step: usize) -> u64 {
+    reduce(scramble(!(result as u64) ^ step as u64), ALPHABET_SIZE)
+}
+
+/// Generate `number` positions to access, following the access pattern.
+fn create_access_queries(number: usize, len: usize, pattern: AccessPattern, rng: &mut StdRng) -> Box<[usize]> {
+    pattern.positions(number, len, rng)
+}
+
+/// Generate `number` queries for the occurrences of uniformly random symbols before the positions
+/// chosen by the access pattern.
+fn create_rank_queries(number: usize, len: usize, pattern: AccessPattern, rng: &mut StdRng) -> Box<[(usize, u64)]> {
+    let positions = pattern.positions(number, len, rng);
+    positions.iter().map(|&position| (position, rng.gen_range(0..ALPHABET_SIZE))).collect()
+}
+
+/// The maximum number of select queries precomputed from the input of the select runners.
+const SELECT_QUERIES: usize = 1 << 16;
+
+/// The number of select queries precomputed for a sequence of `len` symbols.
+fn select_queries(len: usize) -> usize {
+    len.min(SELECT_QUERIES)
+}
+
+/// A wavelet matrix together with select queries that are valid for its sequence.
+pub(crate) struct SelectContext<W> {
+    wavelet: W,
+
+    /// Pairs of a rank and a symbol occurring more often than the rank, sorted by the position of
+    /// the answer.
+    queries: Box<[(usize, u64)]>,
+}
+
+/// Generate a random sequence of `size` symbols like [`random_sequence`], and the select queries for
+/// the occurrences of the symbols at random positions of it. Every query has an answer, unlike queries
+/// for random ranks of random symbols, which mostly exceed the number of occurrences.
+fn random_select_input(size: usize, rng: &mut StdRng) -> (Vec<u64>, Box<[(usize, u64)]>) {
+    let words = random_sequence(size, rng);
+    let mut positions = (0..select_queries(size)).map(|_| rng.gen_range(0..size)).collect::<Vec<_>>();
+    positions.sort_unstable();
+
+    // count the occurrences of every symbol in one pass, stopping at each chosen position
+    let mut occurrences = vec![0; ALPHABET_SIZE as usize];
+    let mut queries = Vec::with_capacity(positions.len());
+    let mut positions = positions.into_iter().peekable();
+    for (position, symbol) in symbols(&words).enumerate() {
+        while positions.next_if_eq(&position).is_some() {
+            queries.push((occurrences[symbol as usize], symbol));
+        }
+        occurrences[symbol as usize] += 1;
+    }
+    (words, queries.into_boxed_slice())
+}
+
+fn build_select_context<W>(input: (Vec<u64>, Box<[(usize, u64)]>), build: impl FnOnce(&[u64]) -> W) -> SelectContext<W> {
+    let (words, queries) = input;
+    SelectContext { wavelet: build(&words), queries }
+}
+
+/// Generate `number` indices of precomputed select queries, following the access pattern. The
+/// queries are sorted by the position of their answer, so the pattern determines the queried positions.
+fn create_select_queries(number: usize, len: usize, pattern: AccessPattern, rng: &mut StdRng) -> Box<[usize]> {
+    pattern.positions(number, select_queries(len), rng)
+}
+
+fn chain_select_query(result: usize, step: usize, len: usize) -> usize {
+    chain_index(result, step, select_queries(len))
+}
+
+/// The non-empty position range `left..right` spanned by the positions `a` and `b`.
+fn span(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b) + 1)
+}
+
+/// A uniformly random non-empty position range `left..right` derived from two random values.
+fn range(a: u64, b: u64, len: usize) -> (usize, usize) {
+    span(reduce(a, len as u64) as usize, reduce(b, len as u64) as usize)
+}
+
+/// Generate `number` random non-empty position ranges. One end of each range follows the access
+/// pattern, and the other one is uniformly random.
+fn create_ranges(number: usize, len: usize, pattern: AccessPattern, rng: &mut StdRng) -> Vec<(usize, usize)> {
+    let positions = pattern.positions(number, len, rng);
+    positions.iter().map(|&position| span(position, rng.gen_range(0..len))).collect()
+}
+
+/// Generate `number` queries for the `k`-th smallest symbol in random position ranges, with `k`
+/// uniformly random within the range.
+fn create_quantile_queries(number: usize, len: usize, pattern: AccessPattern, rng: &mut StdRng) -> Box<[(usize, usize, usize)]> {
+    create_ranges(number, len, pattern, rng)
+        .into_iter()
+        .map(|(left, right)| (left, right, rng.gen_range(0..right - left)))
+        .collect()
+}
+
+fn chain_quantile_query(result: usize, step: usize, len: usize) -> (usize, usize, usize) {
+    let a = scramble(result as u64 ^ step as u64);
+    let b = scramble(a);
+    let (left, right) = range(a, b, len);
+    (left, right, reduce(scramble(b), (right - left) as u64) as usize)
+}
+
+/// Generate `number` queries for the occurrences of uniformly random symbols in random position ranges.
+fn create_range_count_queries(number: usize, len: usize, pattern: AccessPattern, rng: &mut StdRng) -> Box<[(usize, usize, u64)]> {
+    create_ranges(number, len, pattern, rng)
+        .into_iter()
+        .map(|(left, right)| (left, right, rng.gen_range(0..ALPHABET_SIZE)))
+        .collect()
+}
+
+fn chain_range_count_query(result: usize, step: usize, len: usize) -> (usize, usize, u64) {
+    let a = scramble(result as u64 ^ step as u64);
+    let b = scramble(a);
+    let (left, right) = range(a, b, len);
+    (left, right, chain_symbol(result, step))
+}
+
+/// The number of occurrences of a symbol in a position range, computed from two rank queries for
+/// libraries without a dedicated operation.
+fn rank_difference(left: Option<usize>, right: Option<usize>) -> Option<usize> {
+    Some(right? - left?)
+}
+
+/// Define the runners of one operation, sharing the query generation and chaining of all libraries.
+/// Every library is given as `Runner: ContextType = build_function => |context, param| query`.
+macro_rules! wavelet_runners {
+    (params = $params:ident, chain = $chain:expr, param = $param_type:ty, $($runner:ident: $context_type:ty = $build:ident => |$context:ident, $param:ident| $body:expr),+ $(,)?) => {
+        $(
+        runner!(
+            $runner,
+            generate_input = |size, rng| {
+                random_sequence(size, rng)
+            },
+            build = |words: Vec<u64>| {
+                $build(&words)
+            },
+            prepare_params = |number, len, pattern, rng| {
+                $params(number, len, pattern, rng)
+            },
+            execute = |$context: $context_type, $param: $param_type| -> usize {
+                $body.into_output()
+            },
+            chain = |result, step, len| {
+                $chain(result, step, len)
+            }
+        );
+        )+
+    };
+}
+
+wavelet_runners!(
+    params = create_access_queries,
+    chain = chain_index,
+    param = usize,
+    VersAccessRunner: WaveletMatrix = build_vers => |wavelet, index| wavelet.get_u64(*index),
+    CseqAccessRunner: cseq::wavelet_matrix::Sequence = build_cseq => |wavelet, index| wavelet.get(*index),
+    WaveletMatrixAccessRunner: wavelet_matrix::WaveletMatrix = build_wavelet_matrix => |wavelet, index| wavelet.lookup(*index),
+    QwtAccessRunner: QWT512<u64> = build_qwt => |wavelet, index| wavelet.get(*index),
+);
+
+wavelet_runners!(
+    params = create_rank_queries,
+    chain = |result, step, len| (chain_index(result, step, len), chain_symbol(result, step)),
+    param = (usize, u64),
+    VersRankRunner: WaveletMatrix = build_vers => |wavelet, query| wavelet.rank_u64(query.0, query.1),
+    CseqRankRunner: cseq::wavelet_matrix::Sequence = build_cseq => |wavelet, query| wavelet.rank(query.0, query.1),
+    WaveletMatrixRankRunner: wavelet_matrix::WaveletMatrix = build_wavelet_matrix => |wavelet, query| wavelet.rank(query.0, query.1),
+    QwtRankRunner: QWT512<u64> = build_qwt => |wavelet, query| wavelet.rank(query.1, query.0),
+);
+
+/// Define the select runners, which query the precomputed queries of their [`SelectContext`].
+/// Every library is given as `Runner: WaveletType = build_function => |wavelet, rank, symbol| query`.
+macro_rules! wavelet_select_runners {
+    ($($runner:ident: $wavelet_type:ty = $build:ident => |$wavelet:ident, $rank:ident, $symbol:ident| $body:expr),+ $(,)?) => {
+        $(
+        runner!(
+            $runner,
+            generate_input = |size, rng| {
+                random_select_input(size, rng)
+            },
+            build = |input: (Vec<u64>, Box<[(usize, u64)]>)| {
+                build_select_context(input, $build)
+            },
+            prepare_params = |number, len, pattern, rng| {
+                create_select_queries(number, len, pattern, rng)
+            },
+            execute = |context: SelectContext<$wavelet_type>, index: usize| -> usize {
+                let ($rank, $symbol) = context.queries[*index];
+                let $wavelet = &context.wavelet;
+                $body.into_output()
+            },
+            chain = |result, step, len| {
+                chain_select_query(result, step, len)
+            }
+        );
+        )+
+    };
+}
+
+wavelet_select_runners!(
+    VersSelectRunner: WaveletMatrix = build_vers => |wavelet, rank, symbol| wavelet.select_u64(rank, symbol),
+    CseqSelectRunner: cseq::wavelet_matrix::Sequence = build_cseq => |wavelet, rank, symbol| wavelet.select(rank, symbol),
+    WaveletMatrixSelectRunner: wavelet_matrix::WaveletMatrix = build_wavelet_matrix => |wavelet, rank, symbol| wavelet.select(rank, symbol),
+    QwtSelectRunner: QWT512<u64> = build_qwt => |wavelet, rank, symbol| wavelet.select(symbol, rank),
+);
+
+// cseq and qwt do not support quantile queries
+wavelet_runners!(
+    params = create_quantile_queries,
+    chain = chain_quantile_query,
+    param = (usize, usize, usize),
+    VersQuantileRunner: WaveletMatrix = build_vers => |wavelet, query| wavelet.quantile_u64(query.0..query.1, query.2),
+    WaveletMatrixQuantileRunner: wavelet_matrix::WaveletMatrix = build_wavelet_matrix => |wavelet, query| wavelet.quantile(query.0..query.1, query.2),
+);
+
+wavelet_runners!(
+    params = create_range_count_queries,
+    chain = chain_range_count_query,
+    param = (usize, usize, u64),
+    VersRangeCountRunner: WaveletMatrix = build_vers => |wavelet, query| wavelet.rank_range_u64(query.0..query.1, query.2),
+    CseqRangeCountRunner: cseq::wavelet_matrix::Sequence = build_cseq => |wavelet, query| rank_difference(wavelet.rank(query.0, query.2), wavelet.rank(query.1, query.2)),
+    WaveletMatrixRangeCountRunner: wavelet_matrix::WaveletMatrix = build_wavelet_matrix => |wavelet, query| wavelet.count(query.0..query.1, query.2),
+    QwtRangeCountRunner: QWT512<u64> = build_qwt => |wavelet, query| rank_difference(wavelet.rank(query.2, query.0), wavelet.rank(query.2, query.1)),
+);
+
+/// Run the access, rank, select, quantile and range count benchmarks on sequences of `size` uniformly
+/// random 16-bit symbols, each as its own benchmark with its own result file.
+pub(crate) fn benchmark(output_dir: &Path, settings: &Settings) -> io::Result<()> {
+    let sizes = vec![
+        1 << 8,
+        1 << 10,
+        1 << 12,
+        1 << 14,
+        1 << 16,
+        1 << 18,
+        1 << 20,
+        1 << 22,
+        1 << 24,
+        1 << 26,
+        1 << 28,
+    ];
+
+    let mut access = Benchmark::new("Wavelet_Access", sizes.clone()).with_settings(settings);
+    access.add_measurement(Measurement::new(RUNNERS[0], &VersAccessRunner));
+    access.add_measurement(Measurement::new(RUNNERS[1], &CseqAccessRunner));
+    access.add_measurement(Measurement::new(RUNNERS[2], &WaveletMatrixAccessRunner));
+    access.add_measurement(Measurement::new(RUNNERS[3], &QwtAccessRunner));
+    access.benchmark(output_dir)?;
+
+    let mut rank = Benchmark::new("Wavelet_Rank", sizes.clone()).with_settings(settings);
+    rank.add_measurement(Measurement::new(RUNNERS[0], &VersRankRunner));
+    rank.add_measurement(Measurement::new(RUNNERS[1], &CseqRankRunner));
+    rank.add_measurement(Measurement::new(RUNNERS[2], &WaveletMatrixRankRunner));
+    rank.add_measurement(Measurement::new(RUNNERS[3], &QwtRankRunner));
+    rank.benchmark(output_dir)?;
+
+    let mut select = Benchmark::new("Wavelet_Select", sizes.clone()).with_settings(settings);
+    select.add_measurement(Measurement::new(RUNNERS[0], &VersSelectRunner));
+    select.add_measurement(Measurement::new(RUNNERS[1], &CseqSelectRunner));
+    select.add_measurement(Measurement::new(RUNNERS[2], &WaveletMatrixSelectRunner));
+    select.add_measurement(Measurement::new(RUNNERS[3], &QwtSelectRunner));
+    select.benchmark(output_dir)?;
+
+    let mut quantile = Benchmark::new("Wavelet_Quantile", sizes.clone()).with_settings(settings);
+    quantile.add_measurement(Measurement::new(RUNNERS[0], &VersQuantileRunner));
+    quantile.add_measurement(Measurement::new(RUNNERS[2], &WaveletMatrixQuantileRunner));
+    quantile.benchmark(output_dir)?;
+
+    let mut range_count = Benchmark::new("Wavelet_Range_Count", sizes).with_settings(settings);
+    range_count.add_measurement(Measurement::new(RUNNERS[0], &VersRangeCountRunner));
+    range_count.add_measurement(Measurement::new(RUNNERS[1], &CseqRangeCountRunner));
+    range_count.add_measurement(Measurement::new(RUNNERS[2], &WaveletMatrixRangeCountRunner));
+    range_count.add_measurement(Measurement::new(RUNNERS[3], &QwtRangeCountRunner));
+    range_count.benchmark(output_dir)
+}
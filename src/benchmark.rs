@@ -1,9 +1,20 @@
-use crate::measure::Measurement;
-use std::fs::OpenOptions;
-use std::io::Write;
+use crate::cycles;
+use crate::measure::{Measure, Measurement, MeasurementKind};
+use crate::perf::{EVENTS, EVENT_NAMES};
+use crate::settings::Settings;
+use crate::sink::{HistogramRow, ResultRow, SampleRow, Sinks, Status};
+use std::io;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
-const NUM_ROUNDS: usize = 3;
+/// The default number of interleaved rounds measured for every size.
+pub(crate) const NUM_ROUNDS: usize = 3;
+
+/// The largest state size for which the contexts of all runners are kept alive across rounds.
+/// Above this size, each runner drops its context once all of its measurements have sampled a chunk,
+/// so only one data structure is alive at a time and peak memory stays bounded, at the cost of one
+/// rebuild per round.
+pub(crate) const MAX_RETAINED_SIZE: usize = 1 << 28;
 
 /// A benchmark is a collection of ([`Measurements`]) that are run interleaved.
 /// Each measurement defines a [`Runner`] which defines how to execute the benchmarked function.
@@ -13,60 +24,279 @@ const NUM_ROUNDS: usize = 3;
 ///
 /// [`Measurements`]: Measurement
 /// [`Runner`]: crate::runner::Runner
-pub(crate) struct Benchmark<'a, State, Param> {
+pub(crate) struct Benchmark<'a> {
     name: String,
-    runners: Vec<Measurement<'a, State, Param>>,
+    runners: Vec<Box<dyn Measure + 'a>>,
+    /// For every measurement, the index of the runner it belongs to. The measurements of one runner
+    /// are adjacent and share its context.
+    groups: Vec<usize>,
     sizes: Vec<usize>,
+    settings: Settings,
 }
 
-impl<'a, State, Param> Benchmark<'a, State, Param> {
+impl<'a> Benchmark<'a> {
 
     /// Create a new benchmark with a `name` and a list of state sizes. The benchmark is repeated
     /// for all attached [`Measurements`] for each state size.
     ///
     /// [`Measurements`]: Measurement
     pub(crate) fn new(name: &str, sizes: Vec<usize>) -> Self {
-        Self { name: name.to_string(), runners: Vec::new(), sizes }
+        Self { name: name.to_string(), runners: Vec::new(), groups: Vec::new(), sizes, settings: Settings::default() }
     }
 
-    pub(crate) fn add_measurement(&mut self, runner: Measurement<'a, State, Param>) {
-        self.runners.push(runner);
+    /// Apply the harness [`Settings`] to all measurements added after this call.
+    pub(crate) fn with_settings(mut self, settings: &Settings) -> Self {
+        self.settings = settings.clone();
+        self
     }
 
-    pub(crate) fn benchmark(&mut self, output_dir: &Path) {
-        let mut size_index = 0;
+    /// Add a measurement of the runner's queries. Depending on the [`Settings`], companion
+    /// measurements timing batched queries, dependent query chains (if the runner supports them),
+    /// and the construction of the runner's context are added.
+    /// Runners not selected by the library patterns of the settings are skipped.
+    pub(crate) fn add_measurement<State: 'a, Param: 'a, Output: Copy + Default + 'a>(&mut self, runner: Measurement<'a, State, Param, Output>) {
+        if !self.settings.selects_runner(runner.name()) {
+            return;
+        }
+
+        let runner = runner
+            .with_access_pattern(self.settings.access_pattern)
+            .with_seed(self.settings.seed)
+            .with_rebuild_interval(self.settings.rebuild_interval)
+            .with_histogram(self.settings.latency_histograms)
+            .with_perf_counters(self.settings.perf_counters)
+            .with_timing(self.settings.minimum_running_time_nanos(), self.settings.chunk_time_nanos());
+
+        let mut companions: Vec<Box<dyn Measure + 'a>> = Vec::new();
+        if self.settings.measure_throughput {
+            companions.push(Box::new(runner.companion(MeasurementKind::Batch)));
+        }
+        if self.settings.measure_latency && runner.supports(MeasurementKind::Chain) {
+            companions.push(Box::new(runner.companion(MeasurementKind::Chain)));
+        }
+        if self.settings.measure_construction {
+            companions.push(Box::new(runner.companion(MeasurementKind::Build)));
+        }
+
+        let group = self.groups.last().map_or(0, |group| group + 1);
+        self.groups.extend(std::iter::repeat_n(group, companions.len() + 1));
+        self.runners.push(Box::new(runner));
+        self.runners.extend(companions);
+    }
+
+    /// Measure all runners at all sizes, and write the results into the output directory.
+    /// Fails if the output files cannot be opened.
+    pub(crate) fn benchmark(&mut self, output_dir: &Path) -> io::Result<()> {
+        if self.runners.is_empty() {
+            return Ok(());
+        }
+
+        let sizes = self.settings.sizes(&self.sizes);
+        let rounds = self.settings.rounds;
+        let memory_budget = self.settings.memory_budget_bytes();
+
+        let name = self.name.to_lowercase();
+        let mut sinks = Sinks::create::<ResultRow>(output_dir, &name, &self.settings)?;
+
+        let mut histogram_sinks = self
+            .settings
+            .latency_histograms
+            .then(|| Sinks::create::<HistogramRow>(output_dir, &(name.clone() + "_latency"), &self.settings))
+            .transpose()?;
+
+        let mut samples_sinks = self
+            .settings
+            .raw_samples
+            .then(|| Sinks::create::<SampleRow>(output_dir, &(name.clone() + "_samples"), &self.settings))
+            .transpose()?;
 
-        let path = output_dir.join(Path::new(&(self.name.to_owned().to_lowercase() + ".csv")));
-        let mut file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .open(path)
-            .unwrap();
+        // runners that exceeded the timeout at one size are not measured at any larger size
+        let mut dropped = vec![false; self.runners.len()];
+
+        for current_size in sizes {
+            if dropped.iter().all(|&dropped| dropped) {
+                break;
+            }
 
-        // don't borrow the size vec
-        while size_index < self.sizes.len() {
-            let current_size =  self.sizes[size_index];
             println!("Benchmarking {} elements...", current_size);
+            let mut state = SizeState::new(current_size, &dropped);
+
+            // skip runners that do not support the size, or would exceed the memory budget on their own
+            let mut required_memory = vec![0; self.runners.len()];
+            for (index, runner) in self.runners.iter_mut().enumerate() {
+                if !state.active[index] {
+                    continue;
+                }
+
+                if let Some(minimum) = runner.min_size().filter(|&minimum| current_size < minimum) {
+                    state.active[index] = false;
+                    println!("[{}/{}]\t{} ({})\tSkipped, the runner only supports sizes of at least {}", self.name, current_size, label(runner.name(), runner.density()), runner.kind(), minimum);
+                    sinks.write(&marker_row(&self.name, runner.as_ref(), current_size, Status::Unsupported, self.settings.seed));
+                    continue;
+                }
+
+                if let Some(limit) = runner.size_limit().filter(|&limit| current_size >= limit) {
+                    state.active[index] = false;
+                    println!("[{}/{}]\t{} ({})\tSkipped, the runner only supports sizes below {}", self.name, current_size, label(runner.name(), runner.density()), runner.kind(), limit);
+                    sinks.write(&marker_row(&self.name, runner.as_ref(), current_size, Status::Unsupported, self.settings.seed));
+                    continue;
+                }
 
-            for runner in self.runners.iter_mut() {
+                required_memory[index] = runner.required_memory(current_size);
+                if required_memory[index] > memory_budget {
+                    state.active[index] = false;
+                    println!("[{}/{}]\t{} ({})\tSkipped, exceeds the memory budget of {} bytes", self.name, current_size, label(runner.name(), runner.density()), runner.kind(), memory_budget);
+                    sinks.write(&marker_row(&self.name, runner.as_ref(), current_size, Status::Skipped, self.settings.seed));
+                }
+            }
+
+            // only keep all contexts alive if they fit into the memory budget together. The measurements
+            // of a runner share one context, and construction measurements do not keep theirs.
+            let mut retained_memory = vec![0; self.runners.len()];
+            for (index, _) in self.runners.iter().enumerate().filter(|&(index, runner)| state.active[index] && runner.kind() != MeasurementKind::Build) {
+                let group = self.groups[index];
+                retained_memory[group] = retained_memory[group].max(required_memory[index]);
+            }
+            let retain_contexts = current_size <= MAX_RETAINED_SIZE && retained_memory.iter().sum::<usize>() <= memory_budget;
+
+            self.run_step(&mut sinks, &mut state, !retain_contexts, |runner| {
                 runner.initialize_measurement(current_size);
                 runner.estimate_timing();
+            });
+
+            for i in 0..rounds {
+                println!("Round {}/{rounds}...", i + 1);
+                self.run_step(&mut sinks, &mut state, !retain_contexts, |runner| runner.benchmark_chunk());
             }
 
-            for i in 0..NUM_ROUNDS {
-                println!("Round {}/{NUM_ROUNDS}...", i + 1);
-                for runner in self.runners.iter_mut() {
-                    runner.benchmark_chunk();
+            for (runner, _) in self.runners.iter_mut().zip(&state.active).filter(|(_, &active)| active) {
+                runner.release_context();
+                let stats = runner.get_final_measurement();
+                println!("[{}/{}]\t{} ({})\tMean: {:.6} [{:.6}-{:.6} CI],\t Median: {:.6} (MAD {:.6}),\t [{:.6}-{:.6}],\t Std. Dev: {:.6} ({:.3}%),\t Outliers: {} mild, {} severe of {}",
+                         self.name, current_size, label(runner.name(), runner.density()), runner.kind(), stats.mean, stats.ci_lower, stats.ci_upper, stats.median, stats.mad, stats.min, stats.max, stats.std_dev, stats.relative_std_dev() * 100.0, stats.mild_outliers, stats.severe_outliers, stats.samples);
+                let counters = runner.counters().unwrap_or([None; EVENTS]);
+                if counters.iter().any(Option::is_some) {
+                    let per_query = EVENT_NAMES.iter().zip(counters).filter_map(|(name, value)| value.map(|value| format!("{name}: {value:.3}"))).collect::<Vec<_>>();
+                    println!("[{}/{}]\t{} ({})\tPer query: {}", self.name, current_size, label(runner.name(), runner.density()), runner.kind(), per_query.join(", "));
+                }
+                sinks.write(&ResultRow { stats: Some(&stats), counters, ..marker_row(&self.name, runner.as_ref(), current_size, Status::Ok, self.settings.seed) });
+
+                if let (Some(histogram), Some(histogram_sinks)) = (runner.histogram(), histogram_sinks.as_mut()) {
+                    println!("[{}/{}]\t{} ({})\tLatency p50: {:.1} ns, p99: {:.1} ns, p99.9: {:.1} ns of {} queries", self.name, current_size, label(runner.name(), runner.density()), runner.kind(),
+                             cycles::ticks_to_nanos(histogram.quantile(0.5)), cycles::ticks_to_nanos(histogram.quantile(0.99)), cycles::ticks_to_nanos(histogram.quantile(0.999)), histogram.total());
+                    for (low, high, count) in histogram.buckets() {
+                        histogram_sinks.write(&HistogramRow {
+                            benchmark: &self.name,
+                            runner: runner.name(),
+                            size: current_size,
+                            density: runner.density(),
+                            kind: runner.kind(),
+                            seed: self.settings.seed,
+                            bounds_ns: (cycles::ticks_to_nanos(low), cycles::ticks_to_nanos(high)),
+                            count,
+                        });
+                    }
+                }
+
+                if let Some(samples_sinks) = samples_sinks.as_mut() {
+                    samples_sinks.write(&SampleRow {
+                        benchmark: &self.name,
+                        runner: runner.name(),
+                        size: current_size,
+                        density: runner.density(),
+                        kind: runner.kind(),
+                        seed: self.settings.seed,
+                        repetitions: runner.repetitions(),
+                        samples: runner.samples(),
+                    });
                 }
             }
 
-            for runner in self.runners.iter_mut() {
-                let (mean, std_dev, rel_std_dev, min, max) = runner.get_final_measurement();
-                println!("[{}/{}]\t{}\tMean: {:.6}\t [{:.6}-{:.6}],\t Std. Dev: {:.6} ({:.3}%)", self.name, current_size, runner.name, mean, min, max, std_dev, rel_std_dev * 100.0);
-                writeln!(file, "{},{},{},{},{},{},{}", self.name, runner.name, current_size, mean, min, max, std_dev).unwrap_or_else(|e| eprintln!("WARNING: {}", e));
+            for (dropped, timed_out) in dropped.iter_mut().zip(state.timed_out) {
+                *dropped |= timed_out;
             }
+        }
+
+        Ok(())
+    }
+
+    /// Run `step` on every active runner of the current size. The step stops sampling once the runner
+    /// has spent the configured timeout at the current size. Runners that have spent more than the
+    /// timeout are deactivated, and a marker row is written instead of their results.
+    /// With `release_contexts`, the context of every runner is dropped once the last of its measurements
+    /// querying it has run the step.
+    fn run_step(&mut self, sinks: &mut Sinks, state: &mut SizeState, release_contexts: bool, step: impl Fn(&mut dyn Measure)) {
+        let timeout = self.settings.runner_timeout();
+
+        for index in 0..self.runners.len() {
+            if !state.active[index] {
+                continue;
+            }
+
+            let runner = &mut self.runners[index];
+            let start = Instant::now();
+            runner.set_deadline(start + timeout.saturating_sub(state.spent[index]));
+            step(runner.as_mut());
+            state.spent[index] += start.elapsed();
+
+            let timed_out = state.spent[index] > timeout;
+            if (release_contexts || timed_out) && !self.context_needed_later(index, &state.active) {
+                self.runners[index].release_context();
+            }
+
+            if timed_out {
+                let runner = &self.runners[index];
+                state.active[index] = false;
+                state.timed_out[index] = true;
+                println!("[{}/{}]\t{} ({})\tDropped from all remaining sizes, exceeded the timeout of {:?} (spent {:?})", self.name, state.size, label(runner.name(), runner.density()), runner.kind(), timeout, state.spent[index]);
+                sinks.write(&marker_row(&self.name, runner.as_ref(), state.size, Status::Timeout, self.settings.seed));
+            }
+        }
+    }
+
+    /// Whether a measurement after the one at `index` shares its context and still queries it at the
+    /// current size.
+    fn context_needed_later(&self, index: usize, active: &[bool]) -> bool {
+        (index + 1..self.runners.len())
+            .take_while(|&later| self.groups[later] == self.groups[index])
+            .any(|later| active[later] && self.runners[later].kind() != MeasurementKind::Build)
+    }
+}
 
-            size_index += 1;
+/// The row of a runner at one size without statistics or counters, e.g. for a runner that was not measured.
+fn marker_row<'r>(benchmark: &'r str, runner: &'r dyn Measure, size: usize, status: Status, seed: u64) -> ResultRow<'r> {
+    ResultRow { benchmark, runner: runner.name(), size, density: runner.density(), kind: runner.kind(), status, seed, stats: None, counters: [None; EVENTS] }
+}
+
+/// The name of a runner in the console output, including the density of its input if it has one.
+pub(crate) fn label(name: &str, density: Option<f64>) -> String {
+    match density {
+        Some(density) => format!("{} [density {}]", name, density),
+        None => name.to_string(),
+    }
+}
+
+/// Bookkeeping of the runners measured at one size.
+struct SizeState {
+    size: usize,
+
+    /// Whether each runner is still measured at this size.
+    active: Vec<bool>,
+
+    /// The wall-clock time each runner has spent at this size.
+    spent: Vec<Duration>,
+
+    /// Whether each runner exceeded the timeout at this size.
+    timed_out: Vec<bool>,
+}
+
+impl SizeState {
+    fn new(size: usize, dropped: &[bool]) -> Self {
+        Self {
+            size,
+            active: dropped.iter().map(|&dropped| !dropped).collect(),
+            spent: vec![Duration::ZERO; dropped.len()],
+            timed_out: vec![false; dropped.len()],
         }
     }
-}
\ No newline at end of file
+}
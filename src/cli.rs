@@ -0,0 +1,125 @@
+use crate::compare::{DEFAULT_SIGNIFICANCE, DEFAULT_THRESHOLD};
+use crate::plot::{ErrorBars, DEFAULT_SPLIT_RATIO};
+use crate::settings::Settings;
+use clap::{Parser, Subcommand};
+use glob::Pattern;
+use std::path::PathBuf;
+
+/// The directory the measurements are written to by default.
+const MEASUREMENTS_DIR: &str = "./measurements";
+
+/// Benchmark harness comparing vers against other succinct data structure libraries.
+#[derive(Parser, Debug)]
+#[command(version)]
+pub(crate) struct Cli {
+    #[command(subcommand)]
+    pub(crate) command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Run the selected benchmark suites.
+    Run(Box<RunArgs>),
+
+    /// List all available suites and their runners.
+    List,
+
+    /// Render result files to SVG plots.
+    Plot(PlotArgs),
+
+    /// Compare two measurement directories and report regressions. Exits with status 2 if any
+    /// measurement regressed.
+    Compare(CompareArgs),
+
+    /// Recompute the statistics of a raw sample file written with `--raw-samples`.
+    Analyze(AnalyzeArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct RunArgs {
+    /// Only run suites whose name matches one of these glob patterns. By default, all suites are run.
+    #[arg(long = "suite", value_name = "GLOB")]
+    pub(crate) suites: Vec<Pattern>,
+
+    /// The directory the measurements are written to.
+    #[arg(long, default_value = MEASUREMENTS_DIR)]
+    pub(crate) output_dir: PathBuf,
+
+    #[command(flatten)]
+    pub(crate) settings: Settings,
+}
+
+impl RunArgs {
+    /// Whether the suite with the given name is selected by the suite patterns.
+    pub(crate) fn selects_suite(&self, name: &str) -> bool {
+        self.suites.is_empty() || self.suites.iter().any(|pattern| pattern.matches(name))
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct PlotArgs {
+    /// The result files to plot, written as CSV or JSON lines by the `run` command.
+    #[arg(required = true, value_name = "FILE")]
+    pub(crate) inputs: Vec<PathBuf>,
+
+    /// The directory the plots are written to. Defaults to the directory of each result file.
+    #[arg(long)]
+    pub(crate) output_dir: Option<PathBuf>,
+
+    /// The measurement kinds to plot, each into its own plot.
+    #[arg(long = "kind", value_delimiter = ',', default_value = "query")]
+    pub(crate) kinds: Vec<String>,
+
+    /// The range shown by the error bar of every point.
+    #[arg(long, value_enum, default_value = "ci")]
+    pub(crate) error_bars: ErrorBars,
+
+    /// Only plot the rows of this run. Defaults to the last run in each file.
+    #[arg(long)]
+    pub(crate) run_id: Option<String>,
+
+    /// Split the runners into a plot of the fast and a plot of the slow runners if the slowest runner
+    /// is this many times slower than the fastest.
+    #[arg(long, default_value_t = DEFAULT_SPLIT_RATIO)]
+    pub(crate) split_ratio: f64,
+
+    /// The runner shown in both plots if the runners are split.
+    #[arg(long, default_value = "Vers")]
+    pub(crate) reference: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct CompareArgs {
+    /// The directory of the baseline measurements.
+    pub(crate) baseline: PathBuf,
+
+    /// The directory of the measurements compared against the baseline.
+    pub(crate) candidate: PathBuf,
+
+    /// The relative change of the mean in percent above which a significant change is reported.
+    #[arg(long, value_name = "PERCENT", default_value_t = DEFAULT_THRESHOLD)]
+    pub(crate) threshold: f64,
+
+    /// The significance level of the t-test deciding whether a change is caused by noise.
+    #[arg(long, value_name = "ALPHA", default_value_t = DEFAULT_SIGNIFICANCE)]
+    pub(crate) significance: f64,
+
+    /// Also write the table of all matched measurements as CSV to this file.
+    #[arg(long, value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Print all matched measurements, not only those that changed by more than the threshold.
+    #[arg(long)]
+    pub(crate) all: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct AnalyzeArgs {
+    /// The raw sample file to analyze.
+    #[arg(value_name = "FILE")]
+    pub(crate) input: PathBuf,
+
+    /// Also write the recomputed statistics as CSV in the result schema to this file.
+    #[arg(long, value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+}
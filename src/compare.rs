@@ -0,0 +1,339 @@
+//! Comparison of two measurement directories, e.g. before and after updating vers. Rows are matched by
+//! benchmark, runner, kind, size and density, and a change of the mean is reported if it exceeds the threshold
+//! and is statistically significant according to Welch's t-test.
+
+use crate::cli::CompareArgs;
+use crate::results::{self, ResultRecord};
+use crate::statistics::Statistics;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// The default relative change of the mean in percent above which a change is reported.
+pub(crate) const DEFAULT_THRESHOLD: f64 = 5.0;
+
+/// The default significance level of the t-test.
+pub(crate) const DEFAULT_SIGNIFICANCE: f64 = 0.01;
+
+/// The exit code of the `compare` command if any measurement regressed.
+const REGRESSION_EXIT_CODE: i32 = 2;
+
+/// The columns of the exported comparison table.
+const COLUMNS: [&str; 10] = ["benchmark", "runner", "kind", "size", "density", "baseline_mean_ns", "candidate_mean_ns", "change_percent", "p_value", "verdict"];
+
+/// Identifies the measurement of one runner at one size and, for runners configured with one, one
+/// density. The density is kept as written, so it is empty for runners without one.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Key {
+    benchmark: String,
+    runner: String,
+    kind: String,
+    size: u64,
+    density: String,
+}
+
+/// The outcome of comparing a measurement of the candidate with the baseline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Verdict {
+    /// The candidate is significantly slower by more than the threshold.
+    Regression,
+
+    /// The candidate is significantly faster by more than the threshold.
+    Improvement,
+
+    /// The means differ by more than the threshold, but the difference is not significant.
+    Noise,
+
+    /// The means differ by at most the threshold.
+    Unchanged,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Regression => write!(f, "regression"),
+            Verdict::Improvement => write!(f, "improvement"),
+            Verdict::Noise => write!(f, "noise"),
+            Verdict::Unchanged => write!(f, "unchanged"),
+        }
+    }
+}
+
+/// The files of a measurement directory the comparison reads, told apart by their columns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FileKind {
+    /// The result rows with the summary statistics of every measurement.
+    Results,
+
+    /// The raw samples written with `--raw-samples`.
+    Samples,
+}
+
+impl FileKind {
+    /// The kind of a file with the given columns, or `None` for the other files of a run, e.g. the
+    /// latency histograms or the memory sizes.
+    fn of(columns: &[String]) -> Option<Self> {
+        let has = |name: &str| columns.iter().any(|column| column == name);
+        if has("status") && has("mean_ns") {
+            Some(FileKind::Results)
+        } else if has("samples_ns") && has("repetitions") {
+            Some(FileKind::Samples)
+        } else {
+            None
+        }
+    }
+}
+
+struct Comparison {
+    key: Key,
+    baseline: f64,
+    candidate: f64,
+    change: f64,
+    p_value: Option<f64>,
+    verdict: Verdict,
+}
+
+/// Compare the candidate measurements with the baseline, print the changed rows, and export the table
+/// if requested. Returns whether any measurement regressed.
+pub(crate) fn compare(args: &CompareArgs) -> Result<bool, String> {
+    let baseline = load(&args.baseline)?;
+    let candidate = load(&args.candidate)?;
+    let comparisons = comparisons(&baseline, &candidate, args);
+
+    println!("{:<12} {:<20} {:<6} {:>12} {:>8} {:>14} {:>14} {:>9} {:>9}  verdict", "benchmark", "runner", "kind", "size", "density", "baseline (ns)", "candidate (ns)", "change", "p-value");
+    for comparison in comparisons.iter().filter(|comparison| args.all || comparison.verdict != Verdict::Unchanged) {
+        let Comparison { key, baseline, candidate, change, p_value, verdict } = comparison;
+        let p_value = p_value.map_or("-".to_string(), |p| format!("{:.4}", p));
+        println!("{:<12} {:<20} {:<6} {:>12} {:>8} {:>14.3} {:>14.3} {:>+8.2}% {:>9}  {}", key.benchmark, key.runner, key.kind, key.size, key.density, baseline, candidate, change * 100.0, p_value, verdict);
+    }
+
+    let count = |verdict| comparisons.iter().filter(|comparison| comparison.verdict == verdict).count();
+    println!(
+        "{} regressions, {} improvements, {} noisy and {} unchanged of {} matched measurements ({} only in the baseline, {} only in the candidate)",
+        count(Verdict::Regression),
+        count(Verdict::Improvement),
+        count(Verdict::Noise),
+        count(Verdict::Unchanged),
+        comparisons.len(),
+        baseline.keys().filter(|key| !candidate.contains_key(key)).count(),
+        candidate.keys().filter(|key| !baseline.contains_key(key)).count(),
+    );
+
+    if let Some(output) = &args.output {
+        export(output, &comparisons).map_err(|e| format!("Could not write {}: {}", output.display(), e))?;
+    }
+
+    Ok(count(Verdict::Regression) > 0)
+}
+
+/// The exit code of the `compare` command, depending on whether any measurement regressed.
+pub(crate) fn exit_code(regressed: bool) -> i32 {
+    if regressed {
+        REGRESSION_EXIT_CODE
+    } else {
+        0
+    }
+}
+
+/// Compare every measurement of the baseline with the candidate measurement of the same key, if any.
+fn comparisons(baseline: &BTreeMap<Key, Statistics>, candidate: &BTreeMap<Key, Statistics>, args: &CompareArgs) -> Vec<Comparison> {
+    baseline
+        .iter()
+        .filter_map(|(key, baseline)| {
+            let candidate = candidate.get(key)?;
+            let change = candidate.mean / baseline.mean - 1.0;
+            let p_value = baseline.welch_p_value(candidate);
+            let verdict = if change.abs() * 100.0 <= args.threshold {
+                Verdict::Unchanged
+            } else if p_value.is_none_or(|p| p >= args.significance) {
+                Verdict::Noise
+            } else if change > 0.0 {
+                Verdict::Regression
+            } else {
+                Verdict::Improvement
+            };
+            Some(Comparison { key: key.clone(), baseline: baseline.mean, candidate: candidate.mean, change, p_value, verdict })
+        })
+        .collect()
+}
+
+/// Load the statistics of all measured rows of the result files in a directory. Only the last run of
+/// every file is used, and files are read in the order of their names, so timestamped files of later
+/// runs replace the measurements of earlier runs. The statistics are recomputed from the raw samples
+/// of the same run where they were written, and taken from the summary columns otherwise.
+fn load(dir: &Path) -> Result<BTreeMap<Key, Statistics>, String> {
+    let mut paths = fs::read_dir(dir)
+        .map_err(|e| format!("Could not read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "csv" || extension == "jsonl"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut results = Vec::new();
+    let mut samples = BTreeMap::new();
+    for path in paths {
+        let columns = results::columns(&path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let Some(kind) = FileKind::of(&columns) else {
+            continue;
+        };
+
+        let records = results::read(&path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let run_id = records.last().map(|record| record.text("run_id").to_string()).unwrap_or_default();
+        let records = records.into_iter().filter(|record| record.text("run_id") == run_id);
+
+        match kind {
+            FileKind::Results => results.extend(records),
+            FileKind::Samples => {
+                for record in records {
+                    if let (Some(key), Some(statistics)) = (key(&record), record.statistics_from_samples()) {
+                        samples.insert((key, run_id.clone()), statistics);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut measurements = BTreeMap::new();
+    for record in results {
+        let Some(key) = key(&record) else {
+            continue;
+        };
+        let raw = samples.get(&(key.clone(), record.text("run_id").to_string())).cloned();
+        if let Some(statistics) = raw.or_else(|| record.statistics()) {
+            measurements.insert(key, statistics);
+        }
+    }
+    Ok(measurements)
+}
+
+fn key(record: &ResultRecord) -> Option<Key> {
+    Some(Key {
+        benchmark: record.text("benchmark").to_string(),
+        runner: record.text("runner").to_string(),
+        kind: record.text("kind").to_string(),
+        size: record.number("size")? as u64,
+        density: record.text("density").to_string(),
+    })
+}
+
+fn export(path: &Path, comparisons: &[Comparison]) -> std::io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    writeln!(file, "{}", COLUMNS.join(","))?;
+    for Comparison { key, baseline, candidate, change, p_value, verdict } in comparisons {
+        let p_value = p_value.map_or(String::new(), |p| p.to_string());
+        writeln!(file, "{},{},{},{},{},{},{},{},{},{}", key.benchmark, key.runner, key.kind, key.size, key.density, baseline, candidate, change * 100.0, p_value, verdict)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure::MeasurementKind;
+    use crate::perf::EVENTS;
+    use crate::sink::{CsvSink, ResultRow, ResultSink, Row, Run, SampleRow, Status};
+    use std::path::PathBuf;
+
+    const RUN: Run = Run { id: "run", metadata: "metadata.json" };
+
+    /// An empty directory for the files of one test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vers_benchmarks_compare_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn args(baseline: &Path, candidate: &Path) -> CompareArgs {
+        CompareArgs {
+            baseline: baseline.to_path_buf(),
+            candidate: candidate.to_path_buf(),
+            threshold: DEFAULT_THRESHOLD,
+            significance: DEFAULT_SIGNIFICANCE,
+            output: None,
+            all: false,
+        }
+    }
+
+    /// Write a result file with one measured row per runner, with the statistics of the given samples of
+    /// one repetition each.
+    fn write_results(dir: &Path, runners: &[(&str, &[u64])]) {
+        let mut sink = CsvSink::create_file(&dir.join("rank.csv"), ResultRow::COLUMNS).unwrap();
+        for &(runner, samples) in runners {
+            let stats = Statistics::from_raw_samples(samples, 1);
+            let row = ResultRow {
+                benchmark: "Rank",
+                runner,
+                size: 1024,
+                density: Some(0.5),
+                kind: MeasurementKind::Query,
+                status: Status::Ok,
+                seed: 1,
+                stats: Some(&stats),
+                counters: [None; EVENTS],
+            };
+            sink.write(&row.values(RUN)).unwrap();
+        }
+    }
+
+    /// Write a raw sample file for one runner.
+    fn write_samples(dir: &Path, runner: &str, samples: &[u64]) {
+        let mut sink = CsvSink::create_file(&dir.join("rank_samples.csv"), SampleRow::COLUMNS).unwrap();
+        let row = SampleRow { benchmark: "Rank", runner, size: 1024, density: Some(0.5), kind: MeasurementKind::Query, seed: 1, repetitions: 1, samples };
+        sink.write(&row.values(RUN)).unwrap();
+    }
+
+    #[test]
+    fn rows_are_matched_by_key() {
+        let (baseline, candidate) = (test_dir("matched_baseline"), test_dir("matched_candidate"));
+        write_results(&baseline, &[("Vers", &[100, 101, 99]), ("Sux", &[100, 101, 99])]);
+        write_results(&candidate, &[("Vers", &[100, 101, 99]), ("Bitm", &[100, 101, 99])]);
+        fs::write(candidate.join("memory.csv"), "benchmark,runner,support,size,heap_bytes\nMemory,Sux,rank,1024,200\n").unwrap();
+
+        let (baseline, candidate) = (load(&baseline).unwrap(), load(&candidate).unwrap());
+        assert_eq!(candidate.len(), 2, "only the result rows are loaded");
+        let comparisons = comparisons(&baseline, &candidate, &args(Path::new(""), Path::new("")));
+        assert_eq!(comparisons.len(), 1);
+        assert_eq!(comparisons[0].key.runner, "Vers");
+        assert_eq!(comparisons[0].key.density, "0.5");
+        assert_eq!(comparisons[0].verdict, Verdict::Unchanged);
+    }
+
+    #[test]
+    fn regression_needs_significance() {
+        let (baseline, candidate) = (test_dir("significance_baseline"), test_dir("significance_candidate"));
+        write_results(&baseline, &[("Vers", &[100, 101, 99, 100]), ("Sux", &[100, 101, 99, 100])]);
+        write_results(&candidate, &[("Vers", &[120, 121, 119, 120]), ("Sux", &[60, 180, 90, 150])]);
+
+        let (baseline, candidate) = (load(&baseline).unwrap(), load(&candidate).unwrap());
+        let comparisons = comparisons(&baseline, &candidate, &args(Path::new(""), Path::new("")));
+        let verdict = |runner: &str| comparisons.iter().find(|comparison| comparison.key.runner == runner).unwrap().verdict;
+        assert_eq!(verdict("Vers"), Verdict::Regression);
+        assert_eq!(verdict("Sux"), Verdict::Noise);
+    }
+
+    #[test]
+    fn raw_samples_replace_the_summary() {
+        let (baseline, candidate) = (test_dir("samples_baseline"), test_dir("samples_candidate"));
+        write_results(&baseline, &[("Vers", &[100, 101, 99, 100])]);
+        write_results(&candidate, &[("Vers", &[120, 121, 119, 120])]);
+        write_samples(&candidate, "Vers", &[60, 180, 90, 150, 120]);
+
+        let candidate = load(&candidate).unwrap();
+        assert_eq!(candidate.values().next().unwrap().samples, 5, "the statistics are recomputed from the samples");
+        let comparisons = comparisons(&load(&baseline).unwrap(), &candidate, &args(Path::new(""), Path::new("")));
+        assert_eq!(comparisons[0].verdict, Verdict::Noise);
+    }
+
+    #[test]
+    fn regression_exits_with_code_two() {
+        let (baseline, candidate) = (test_dir("exit_baseline"), test_dir("exit_candidate"));
+        write_results(&baseline, &[("Vers", &[100, 101, 99, 100])]);
+        write_results(&candidate, &[("Vers", &[120, 121, 119, 120])]);
+        assert_eq!(compare(&args(&baseline, &candidate)).map(exit_code), Ok(2));
+        assert_eq!(compare(&args(&baseline, &baseline)).map(exit_code), Ok(0));
+        assert_eq!(compare(&args(&candidate, &baseline)).map(exit_code), Ok(0));
+    }
+}
@@ -0,0 +1,79 @@
+//! A low-overhead cycle counter for timing individual queries. On x86-64 it reads the time stamp
+//! counter, on aarch64 the virtual counter, and elsewhere it falls back to [`Instant`].
+
+use std::hint::black_box;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// How long the counter is compared against the system clock to determine its frequency.
+const CALIBRATION_TIME: Duration = Duration::from_millis(50);
+
+/// How many back-to-back reads are used to determine the overhead of reading the counter.
+const OVERHEAD_READS: usize = 10_000;
+
+/// Read the counter. Reads are fenced, so the timed code cannot be reordered around them.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+pub(crate) fn now() -> u64 {
+    use std::arch::x86_64::{_mm_lfence, _rdtsc};
+    // SAFETY: the time stamp counter and lfence are available on every x86-64 CPU
+    unsafe {
+        _mm_lfence();
+        let ticks = _rdtsc();
+        _mm_lfence();
+        ticks
+    }
+}
+
+/// Read the counter. Reads are fenced, so the timed code cannot be reordered around them.
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+pub(crate) fn now() -> u64 {
+    let ticks: u64;
+    // SAFETY: the virtual counter is readable from user space on all supported operating systems
+    unsafe {
+        std::arch::asm!("isb", "mrs {}, cntvct_el0", "isb", out(reg) ticks, options(nostack));
+    }
+    ticks
+}
+
+/// Read the counter, which counts nanoseconds since its first use on this architecture.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[inline(always)]
+pub(crate) fn now() -> u64 {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// The number of counter ticks per nanosecond, calibrated against the system clock on first use.
+pub(crate) fn ticks_per_nano() -> f64 {
+    static TICKS_PER_NANO: OnceLock<f64> = OnceLock::new();
+    *TICKS_PER_NANO.get_or_init(|| {
+        let start_time = Instant::now();
+        let start_ticks = now();
+        while start_time.elapsed() < CALIBRATION_TIME {}
+        let ticks = now() - start_ticks;
+        ticks as f64 / start_time.elapsed().as_nanos() as f64
+    })
+}
+
+/// The minimum number of ticks between two consecutive reads, which is subtracted from every
+/// timed query.
+pub(crate) fn overhead() -> u64 {
+    static OVERHEAD: OnceLock<u64> = OnceLock::new();
+    *OVERHEAD.get_or_init(|| {
+        (0..OVERHEAD_READS)
+            .map(|_| {
+                let start = now();
+                black_box(());
+                now().saturating_sub(start)
+            })
+            .min()
+            .unwrap_or(0)
+    })
+}
+
+/// Convert a number of ticks into nanoseconds.
+pub(crate) fn ticks_to_nanos(ticks: u64) -> f64 {
+    ticks as f64 / ticks_per_nano()
+}
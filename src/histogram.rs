@@ -0,0 +1,144 @@
+/// The number of bits of precision of each bucket. Every power of two is divided into
+/// `2^SUB_BUCKET_BITS` buckets, so the relative error of a recorded value is below 3.2%.
+pub(crate) const SUB_BUCKET_BITS: u32 = 5;
+
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+
+/// The number of buckets needed to cover all 64 bit values.
+const BUCKETS: usize = ((64 - SUB_BUCKET_BITS as usize) + 1) << SUB_BUCKET_BITS;
+
+/// A histogram with logarithmically growing buckets of constant relative precision,
+/// in the style of an HDR histogram. Values below `2^SUB_BUCKET_BITS` are recorded exactly.
+#[derive(Clone, Debug)]
+pub(crate) struct Histogram {
+    counts: Box<[u64]>,
+    total: u64,
+}
+
+impl Histogram {
+    pub(crate) fn new() -> Self {
+        Self {
+            counts: vec![0; BUCKETS].into_boxed_slice(),
+            total: 0,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn record(&mut self, value: u64) {
+        self.counts[Self::index(value)] += 1;
+        self.total += 1;
+    }
+
+    /// The number of recorded values.
+    pub(crate) fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Iterate over all non-empty buckets as the inclusive lower bound, exclusive upper bound, and
+    /// number of values in the bucket.
+    pub(crate) fn buckets(&self) -> impl Iterator<Item = (u64, u64, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(index, &count)| {
+                let (low, high) = Self::bounds(index);
+                (low, high, count)
+            })
+    }
+
+    /// The lower bound of the bucket containing the `fraction` quantile of the recorded values.
+    pub(crate) fn quantile(&self, fraction: f64) -> u64 {
+        let target = (fraction * self.total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (low, _, count) in self.buckets() {
+            seen += count;
+            if seen >= target {
+                return low;
+            }
+        }
+        0
+    }
+
+    #[inline]
+    fn index(value: u64) -> usize {
+        if value < SUB_BUCKETS {
+            value as usize
+        } else {
+            let shift = 63 - value.leading_zeros() - SUB_BUCKET_BITS;
+            (((shift + 1) as u64) << SUB_BUCKET_BITS) as usize + ((value >> shift) - SUB_BUCKETS) as usize
+        }
+    }
+
+    fn bounds(index: usize) -> (u64, u64) {
+        if (index as u64) < SUB_BUCKETS {
+            (index as u64, index as u64 + 1)
+        } else {
+            let shift = (index >> SUB_BUCKET_BITS) as u32 - 1;
+            let sub_bucket = (index as u64 & (SUB_BUCKETS - 1)) + SUB_BUCKETS;
+            let high = ((sub_bucket as u128 + 1) << shift).min(u64::MAX as u128) as u64;
+            (sub_bucket << shift, high)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether `value` lies within the bounds of the bucket `index`. The upper bound of the last bucket
+    /// saturates at `u64::MAX`, so that bucket includes it.
+    fn contains(index: usize, value: u64) -> bool {
+        let (low, high) = Histogram::bounds(index);
+        low <= value && (value < high || high == u64::MAX)
+    }
+
+    #[test]
+    fn small_values_are_exact() {
+        assert_eq!(SUB_BUCKET_BITS, 5);
+        for value in 0..SUB_BUCKETS {
+            assert_eq!(Histogram::index(value), value as usize);
+            assert_eq!(Histogram::bounds(value as usize), (value, value + 1));
+        }
+    }
+
+    #[test]
+    fn boundary_at_sub_bucket_count() {
+        assert_eq!(Histogram::index(31), 31);
+        assert_eq!(Histogram::index(32), 32);
+        assert_eq!(Histogram::bounds(32), (32, 33));
+        assert_eq!(Histogram::index(63), 63);
+        assert_eq!(Histogram::index(64), 64);
+        assert_eq!(Histogram::bounds(64), (64, 66));
+        assert_eq!(Histogram::index(65), 64);
+    }
+
+    #[test]
+    fn largest_value_maps_to_last_bucket() {
+        assert_eq!(BUCKETS, 1920);
+        assert_eq!(Histogram::index(u64::MAX), 1919);
+        assert_eq!(Histogram::bounds(1919), (63 << 58, u64::MAX));
+        assert!(contains(1919, u64::MAX));
+    }
+
+    #[test]
+    fn index_and_bounds_round_trip() {
+        for index in 0..BUCKETS {
+            let (low, high) = Histogram::bounds(index);
+            assert!(low < high, "bucket {index} is empty");
+            assert_eq!(Histogram::index(low), index);
+            assert_eq!(Histogram::index(high - 1), index);
+            if index + 1 < BUCKETS {
+                assert_eq!(Histogram::bounds(index + 1).0, high, "bucket {index} is not adjacent to the next");
+            }
+        }
+
+        let mut value = 1u64;
+        while value != 0 {
+            for probe in [value - 1, value, value + value / 3, value.wrapping_mul(2).wrapping_sub(1)] {
+                assert!(contains(Histogram::index(probe), probe), "{probe} is not within its bucket");
+            }
+            value = value.wrapping_shl(1);
+        }
+    }
+}
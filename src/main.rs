@@ -1,23 +1,86 @@
 use std::fs;
-use std::path::Path;
 use std::process::exit;
-use crate::benches::*;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use crate::benches::SUITES;
+use crate::cli::{Cli, Command, RunArgs};
 
+mod alloc;
+mod analyze;
 mod measure;
+mod metadata;
+mod perf;
+mod plot;
 mod benchmark;
 mod benches;
+mod cli;
+mod compare;
+mod cycles;
+mod histogram;
+mod results;
 mod runner;
+mod settings;
+mod sink;
+mod statistics;
 
-const MEASUREMENTS_DIR: &str = "./measurements";
+#[global_allocator]
+static ALLOCATOR: alloc::TrackingAllocator = alloc::TrackingAllocator;
 
 fn main() {
-    let directory = Path::new(MEASUREMENTS_DIR);
-    fs::create_dir_all(directory)
+    match Cli::parse().command {
+        Command::Run(args) => run(*args),
+        Command::List => list(),
+        Command::Plot(args) => plot::plot(&args).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1);
+        }),
+        Command::Compare(args) => match compare::compare(&args) {
+            Ok(regressed) => exit(compare::exit_code(regressed)),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        },
+        Command::Analyze(args) => analyze::analyze(&args).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1);
+        }),
+    }
+}
+
+fn run(args: RunArgs) {
+    if let Err(e) = args.settings.validate() {
+        Cli::command().error(ErrorKind::ArgumentConflict, e).exit();
+    }
+
+    fs::create_dir_all(&args.output_dir)
         .unwrap_or_else(|e| {
             eprintln!("Could not create measurements directory: {}", e);
             exit(1);
         });
 
-    rank::benchmark(&directory);
-    select::benchmark(&directory);
-}
\ No newline at end of file
+    let settings = &args.settings;
+    println!("Using seed {}", settings.seed);
+    println!("Run id {}", settings.run_id);
+
+    let suites = SUITES.iter().filter(|suite| args.selects_suite(suite.name)).collect::<Vec<_>>();
+    let suite_names = suites.iter().map(|suite| suite.name).collect::<Vec<_>>();
+    metadata::write(&args.output_dir, settings, &suite_names)
+        .unwrap_or_else(|e| eprintln!("WARNING: Could not write the run metadata: {}", e));
+
+    for suite in suites {
+        (suite.run)(&args.output_dir, settings).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1);
+        });
+    }
+}
+
+fn list() {
+    for suite in SUITES {
+        println!("{}\t{}", suite.name, suite.description);
+        for runner in (suite.runners)() {
+            println!("\t{}", runner);
+        }
+    }
+}
@@ -1,39 +1,535 @@
+use crate::alloc;
+use crate::cycles;
+use crate::histogram::Histogram;
+use crate::perf::{PerfCounters, EVENTS};
 use crate::runner::Runner;
+use crate::statistics::Statistics;
+use rand::rngs::StdRng;
+use rand::distributions::Uniform;
+use rand::{Rng, SeedableRng};
+use std::cell::{Cell, RefCell};
+use std::fmt::{Display, Formatter};
+use std::hint::black_box;
+use std::rc::Rc;
+use std::str::FromStr;
 use std::time::Instant;
 
-/// The minimum time any measurement has to run to avoid noise in nanoseconds. Currently, 100 milliseconds.
+/// The default minimum time any measurement has to run to avoid noise in nanoseconds. Currently, 100 milliseconds.
 /// The benchmark repeats the measured function to reach this target time, and averages out the
 /// runtime.
-const MINIMUM_RUNNING_TIME: u64 = 100_000_000;
+pub(crate) const MINIMUM_RUNNING_TIME: u64 = 100_000_000;
 
-/// How long each chunk of the benchmark should run in nanoseconds. Currently, 5 seconds.
-const CHUNK_TIME: u64 = 5_000_000_000;
+/// How long each chunk of the benchmark should run by default in nanoseconds. Currently, 5 seconds.
+pub(crate) const CHUNK_TIME: u64 = 5_000_000_000;
+
+/// The size of the context built to estimate the memory a runner needs per element.
+pub(crate) const ESTIMATION_SIZE: usize = 1 << 20;
+
+/// Mixed into the seed of the query parameter stream, so it is independent of the input stream.
+const PARAMS_STREAM: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Create the rng generating the input of all data structures with `size` elements. It only depends on
+/// the global seed and the size, so every runner builds its context from identical input.
+pub(crate) fn input_rng(seed: u64, size: usize) -> StdRng {
+    StdRng::seed_from_u64(seed ^ size as u64)
+}
+
+/// Create the rng generating the query parameters for data structures with `size` elements.
+fn params_rng(seed: u64, size: usize) -> StdRng {
+    StdRng::seed_from_u64(seed ^ size as u64 ^ PARAMS_STREAM)
+}
+
+/// How the query parameters prepared by a runner are spread over the positions of its data structure.
+/// Runners pass the pattern to [`AccessPattern::positions`] to generate the positions they query.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum AccessPattern {
+    /// Query ascending positions, starting at a random position and wrapping around at the end.
+    Sequential,
+
+    /// Query uniformly random positions.
+    Shuffled,
+
+    /// Query every position that is the given stride after the previous one, starting at a random
+    /// position and wrapping around at the end. The stride is not zero. If it is a multiple of the
+    /// number of positions, ascending positions are queried instead.
+    Strided(usize),
+}
+
+impl AccessPattern {
+    /// Generate `number` positions in `0..len` following this access pattern, drawing all randomness
+    /// from `rng`.
+    pub(crate) fn positions(self, number: usize, len: usize, rng: &mut StdRng) -> Box<[usize]> {
+        match self {
+            AccessPattern::Sequential => walk(number, len, 1, rng),
+            AccessPattern::Shuffled => rng.sample_iter(Uniform::new(0, len)).take(number).collect(),
+            // a stride that is a multiple of the length would query the same position over and over
+            AccessPattern::Strided(stride) => walk(number, len, Some(stride % len).filter(|&step| step != 0).unwrap_or(1), rng),
+        }
+    }
+}
+
+/// Generate `number` positions in `0..len`, each `step` after the previous one modulo `len`, starting at
+/// a random position.
+fn walk(number: usize, len: usize, step: usize, rng: &mut StdRng) -> Box<[usize]> {
+    let mut position = rng.gen_range(0..len);
+    (0..number)
+        .map(|_| {
+            let current = position;
+            position = (position + step) % len;
+            current
+        })
+        .collect()
+}
+
+impl FromStr for AccessPattern {
+    type Err = String;
+
+    /// Parse `sequential`, `shuffled`, or `strided:<stride>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "sequential" => Ok(AccessPattern::Sequential),
+            None if s == "shuffled" => Ok(AccessPattern::Shuffled),
+            Some(("strided", stride)) => match stride.parse() {
+                Ok(0) => Err("the stride must not be zero".to_string()),
+                Ok(stride) => Ok(AccessPattern::Strided(stride)),
+                Err(e) => Err(format!("invalid stride {stride}: {e}")),
+            },
+            _ => Err(format!("unknown access pattern {s}")),
+        }
+    }
+}
+
+/// What a [`Measurement`] times.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MeasurementKind {
+    /// Time the queries executed by [`Runner::execute`] on a prebuilt context, one call at a time.
+    Query,
+
+    /// Time the same queries as [`MeasurementKind::Query`] issued as one independent batch through
+    /// [`Runner::execute_batch`], which measures throughput.
+    Batch,
+
+    /// Time a chain of dependent queries, where each parameter is derived from the previous result
+    /// by [`ChainRunner::chain`], which measures latency.
+    ///
+    /// [`ChainRunner::chain`]: crate::runner::ChainRunner::chain
+    Chain,
+
+    /// Time the construction of the context, excluding the generation of its input.
+    Build,
+}
+
+impl Display for MeasurementKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeasurementKind::Query => write!(f, "query"),
+            MeasurementKind::Batch => write!(f, "batch"),
+            MeasurementKind::Chain => write!(f, "chain"),
+            MeasurementKind::Build => write!(f, "build"),
+        }
+    }
+}
+
+impl FromStr for MeasurementKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "query" => Ok(MeasurementKind::Query),
+            "batch" => Ok(MeasurementKind::Batch),
+            "chain" => Ok(MeasurementKind::Chain),
+            "build" => Ok(MeasurementKind::Build),
+            _ => Err(format!("unknown measurement kind {s}")),
+        }
+    }
+}
+
+/// Type-erased interface of a [`Measurement`], which allows a [`Benchmark`] to interleave measurements
+/// of runners with different context, parameter, and output types.
+///
+/// [`Benchmark`]: crate::benchmark::Benchmark
+pub(crate) trait Measure {
+    fn name(&self) -> &str;
+
+    fn kind(&self) -> MeasurementKind;
+
+    /// The exclusive upper bound of the sizes the runner supports, if it has one.
+    fn size_limit(&self) -> Option<usize>;
+
+    /// The inclusive lower bound of the sizes the runner supports, if it has one.
+    fn min_size(&self) -> Option<usize>;
+
+    /// The density of the runner's input, if it is configured with one.
+    fn density(&self) -> Option<f64>;
+
+    /// Initialize the `Measurement` with a new data structure size, and reset all previously collected
+    /// measurements. A cached context of another size is dropped, while a context of this size built
+    /// by a companion is reused.
+    /// A call to [`estimate_timing`] is necessary before [`benchmark_chunk`] can be called again.
+    ///
+    /// [`estimate_timing`]: Measure::estimate_timing
+    /// [`benchmark_chunk`]: Measure::benchmark_chunk
+    fn initialize_measurement(&mut self, size: usize);
+
+    /// Drop the cached context, so it does not occupy memory while other runners build theirs. The
+    /// context is shared with all companions of the measurement, so it is dropped for them as well.
+    /// The context is rebuilt on demand by the next call to [`estimate_timing`] or [`benchmark_chunk`].
+    ///
+    /// [`estimate_timing`]: Measure::estimate_timing
+    /// [`benchmark_chunk`]: Measure::benchmark_chunk
+    fn release_context(&mut self);
+
+    /// Estimate the peak heap memory in bytes the measurement needs at the given size: one context
+    /// for queries, and the contexts of all repetitions of a sample for construction. Unless the
+    /// runner declares its memory per element, the first call of any companion builds a small
+    /// context to estimate it.
+    fn required_memory(&mut self, size: usize) -> usize;
+
+    /// Stop [`estimate_timing`] and [`benchmark_chunk`] early once the given instant has passed,
+    /// instead of sampling until their target time.
+    ///
+    /// [`estimate_timing`]: Measure::estimate_timing
+    /// [`benchmark_chunk`]: Measure::benchmark_chunk
+    fn set_deadline(&mut self, deadline: Instant);
+
+    fn estimate_timing(&mut self);
+
+    fn benchmark_chunk(&mut self);
+
+    /// The histogram of individual query latencies in counter ticks, if the measurement records one.
+    fn histogram(&self) -> Option<&Histogram>;
+
+    /// The average number of hardware events per query, if the measurement collects performance counters.
+    /// Events the kernel did not count are `None`.
+    fn counters(&self) -> Option<[Option<f64>; EVENTS]>;
+
+    /// The raw samples measured so far, each the total duration in nanoseconds of [`repetitions`] calls.
+    ///
+    /// [`repetitions`]: Measure::repetitions
+    fn samples(&self) -> &[u64];
+
+    /// The number of calls timed by every sample. Calls processing several elements are counted once per
+    /// element, see [`Runner::elements_per_query`].
+    fn repetitions(&self) -> u64;
+
+    /// Get the [`Statistics`] of all samples that have been measured so far, in nanoseconds per call.
+    fn get_final_measurement(&self) -> Statistics;
+}
 
 /// A single benchmark measurement of one function.
 /// The function can be benchmarked multiple times interleaved with other benchmarks.
-pub(crate) struct Measurement<'a, S, P> {
-    func: &'a dyn Runner<Context = S, Param = P>,
-    pub(crate) name: &'a str,
+pub(crate) struct Measurement<'a, S, P, O> {
+    func: &'a dyn Runner<Context = S, Param = P, Output = O>,
+    name: &'a str,
+    kind: MeasurementKind,
     repetitions: u64,
     samples: Vec<u64>,
     size: usize,
+    access_pattern: AccessPattern,
+    /// The context and the size it was built for, shared by all companions of a measurement so it is
+    /// only built once per size.
+    context: Rc<RefCell<Option<(usize, S)>>>,
+    seed: u64,
+    params_rng: StdRng,
+    rebuild_interval: Option<usize>,
+    samples_since_build: usize,
+    results: Vec<O>,
+    minimum_running_time: u64,
+    chunk_time: u64,
+    deadline: Option<Instant>,
+    /// The memory per element of the runner's context, shared by all companions of a measurement so
+    /// it is only estimated once.
+    bytes_per_element: Rc<Cell<Option<f64>>>,
+    histogram: Option<Histogram>,
+    counters: Option<PerfCounters>,
 }
 
-impl<'a, State, Param> Measurement<'a, State, Param> {
-    pub(crate) fn new(name: &'a str, func: &'a dyn Runner<Context=State, Param=Param>) -> Self {
-        Self { func, name, repetitions: 0, samples: Vec::new(), size: 0 }
+impl<'a, State, Param, Output: Copy + Default> Measurement<'a, State, Param, Output> {
+    pub(crate) fn new(name: &'a str, func: &'a dyn Runner<Context=State, Param=Param, Output=Output>) -> Self {
+        Self {
+            func,
+            name,
+            kind: MeasurementKind::Query,
+            repetitions: 0,
+            samples: Vec::new(),
+            size: 0,
+            access_pattern: AccessPattern::Shuffled,
+            context: Rc::new(RefCell::new(None)),
+            seed: 0,
+            params_rng: params_rng(0, 0),
+            rebuild_interval: None,
+            samples_since_build: 0,
+            results: Vec::new(),
+            minimum_running_time: MINIMUM_RUNNING_TIME,
+            chunk_time: CHUNK_TIME,
+            deadline: None,
+            bytes_per_element: Rc::new(Cell::new(None)),
+            histogram: None,
+            counters: None,
+        }
     }
 
-    /// Initialize the `Measurement` with a new data structure size, and reset all previously collected
-    /// measurements.
-    /// A call to [`estimate_timing`] is necessary before [`benchmark`] can be called again.
-    pub(crate) fn initialize_measurement(&mut self, size: usize) {
+    /// Create a new measurement of the given kind for the same runner and with the same configuration.
+    /// The companion shares the context of this measurement.
+    pub(crate) fn companion(&self, kind: MeasurementKind) -> Self {
+        let mut measurement = Self::new(self.name, self.func)
+            .with_access_pattern(self.access_pattern)
+            .with_seed(self.seed)
+            .with_rebuild_interval(self.rebuild_interval)
+            .with_timing(self.minimum_running_time, self.chunk_time);
+        measurement.kind = kind;
+        measurement.bytes_per_element = Rc::clone(&self.bytes_per_element);
+        measurement.context = Rc::clone(&self.context);
+        measurement.with_perf_counters(self.counters.is_some())
+    }
+
+    /// Whether the runner of this measurement supports measurements of the given kind.
+    pub(crate) fn supports(&self, kind: MeasurementKind) -> bool {
+        match kind {
+            MeasurementKind::Chain => self.func.chain_runner().is_some(),
+            _ => true,
+        }
+    }
+
+    /// Set the access pattern the runner prepares its query parameters with. Defaults to
+    /// [`AccessPattern::Shuffled`].
+    pub(crate) fn with_access_pattern(mut self, access_pattern: AccessPattern) -> Self {
+        self.access_pattern = access_pattern;
+        self
+    }
+
+    /// Set the global seed from which the input and the query parameters are generated. Defaults to zero.
+    pub(crate) fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Set the minimum running time of a sample and the duration of a chunk in nanoseconds.
+    /// Defaults to [`MINIMUM_RUNNING_TIME`] and [`CHUNK_TIME`].
+    pub(crate) fn with_timing(mut self, minimum_running_time: u64, chunk_time: u64) -> Self {
+        self.minimum_running_time = minimum_running_time;
+        self.chunk_time = chunk_time;
+        self
+    }
+
+    /// Additionally time every query of one sample per chunk individually with the cycle counter,
+    /// and record the latencies in a [`Histogram`]. Only supported by [`MeasurementKind::Query`].
+    pub(crate) fn with_histogram(mut self, enabled: bool) -> Self {
+        self.histogram = (enabled && self.kind == MeasurementKind::Query).then(Histogram::new);
+        self
+    }
+
+    /// Count hardware events with [`PerfCounters`] around every sample. Not supported by
+    /// [`MeasurementKind::Build`], and silently disabled if the counters cannot be opened.
+    pub(crate) fn with_perf_counters(mut self, enabled: bool) -> Self {
+        self.counters = (enabled && self.kind != MeasurementKind::Build).then(PerfCounters::open).flatten();
+        self
+    }
+
+    /// Rebuild the context every `interval` samples to vary the memory layout of the benchmarked
+    /// data structure. With `None`, the context is built once and reused until it is released.
+    pub(crate) fn with_rebuild_interval(mut self, interval: Option<usize>) -> Self {
+        self.rebuild_interval = interval;
+        self
+    }
+
+    /// Build the context for the current size, unless it is already cached. A context of another size
+    /// is dropped before the new one is built.
+    fn ensure_context(&mut self) {
+        self.drop_stale_context();
+        let mut context = self.context.borrow_mut();
+        if context.is_none() {
+            *context = Some((self.size, self.func.create_context(self.size, &mut input_rng(self.seed, self.size))));
+            self.samples_since_build = 0;
+        }
+    }
+
+    /// Drop the shared context if it was built for another size than the current one.
+    fn drop_stale_context(&self) {
+        let mut context = self.context.borrow_mut();
+        if context.as_ref().is_some_and(|(size, _)| *size != self.size) {
+            *context = None;
+        }
+    }
+
+    /// Build the context for the current size before sampling, unless the measurement does not query
+    /// one.
+    fn prepare_context(&mut self) {
+        if self.kind != MeasurementKind::Build {
+            self.ensure_context();
+        }
+    }
+
+    /// How many elements every timed call processes. Construction is always measured per build.
+    fn elements_per_query(&self) -> u64 {
+        match self.kind {
+            MeasurementKind::Build => 1,
+            _ => self.func.elements_per_query(self.size) as u64,
+        }
+    }
+
+    /// Whether the deadline set by [`Measure::set_deadline`] has passed.
+    fn past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() > deadline)
+    }
+
+    /// Measure the peak heap memory per element needed to build a context of [`ESTIMATION_SIZE`].
+    fn estimate_bytes_per_element(&self) -> f64 {
+        let (context, peak) =
+            alloc::track_peak(|| self.func.create_context(ESTIMATION_SIZE, &mut input_rng(self.seed, ESTIMATION_SIZE)));
+        drop(context);
+
+        peak as f64 / ESTIMATION_SIZE as f64
+    }
+
+    /// Execute one sample worth of queries, timing each query individually, and record the latencies
+    /// in the histogram. The timing overhead of the cycle counter is subtracted from every latency.
+    fn record_histogram(&mut self) {
+        if self.histogram.is_none() {
+            return;
+        }
+
+        self.ensure_context();
+        let params = self.func.prepare_params(self.repetitions as usize, self.size, self.access_pattern, &mut self.params_rng);
+        let elements = self.elements_per_query();
+        let context = self.context.borrow();
+        let state = &context.as_ref().unwrap().1;
+        let histogram = self.histogram.as_mut().unwrap();
+        let overhead = cycles::overhead();
+
+        for param in params.iter() {
+            let start = cycles::now();
+            black_box(self.func.execute(state, param));
+            let end = cycles::now();
+            histogram.record(end.saturating_sub(start).saturating_sub(overhead) / elements);
+        }
+    }
+
+    /// Run a single sample with the given number of repetitions and return its duration in nanoseconds.
+    fn run_sample(&mut self, repetitions: u64) -> u64 {
+        let elements = self.elements_per_query();
+        match self.kind {
+            MeasurementKind::Query => {
+                self.ensure_context();
+                let context = self.context.borrow();
+                let state = &context.as_ref().unwrap().1;
+                let params = self.func.prepare_params(repetitions as usize, self.size, self.access_pattern, &mut self.params_rng);
+
+                start_counters(&mut self.counters);
+                let start = Instant::now();
+                for param in params.iter() {
+                    black_box(self.func.execute(state, param));
+                }
+                let elapsed = start.elapsed().as_nanos() as u64;
+                stop_counters(&mut self.counters, params.len() as u64 * elements);
+                elapsed
+            }
+            MeasurementKind::Batch => {
+                self.ensure_context();
+                let context = self.context.borrow();
+                let state = &context.as_ref().unwrap().1;
+                let params = self.func.prepare_params(repetitions as usize, self.size, self.access_pattern, &mut self.params_rng);
+                // the result buffer is reused across samples, so its pages are not faulted in while timing
+                self.results.resize(params.len(), Output::default());
+
+                start_counters(&mut self.counters);
+                let start = Instant::now();
+                self.func.execute_batch(state, &params, &mut self.results);
+                let elapsed = start.elapsed().as_nanos() as u64;
+                stop_counters(&mut self.counters, params.len() as u64 * elements);
+
+                black_box(&self.results);
+                elapsed
+            }
+            MeasurementKind::Chain => {
+                // chain measurements are only created for runners supporting them
+                let chain = self.func.chain_runner().expect("the runner does not support dependent query chains");
+                self.ensure_context();
+                let context = self.context.borrow();
+                let state = &context.as_ref().unwrap().1;
+                let first = self.func.prepare_params(1, self.size, self.access_pattern, &mut self.params_rng).into_vec().pop().expect("runner prepared no parameters");
+
+                start_counters(&mut self.counters);
+                let start = Instant::now();
+                let last = chain.execute_chain(state, first, repetitions as usize, self.size);
+                let elapsed = start.elapsed().as_nanos() as u64;
+                stop_counters(&mut self.counters, repetitions * elements);
+
+                black_box(last);
+                elapsed
+            }
+            MeasurementKind::Build => self.func.measure_construction(self.size, repetitions as usize, &mut input_rng(self.seed, self.size)),
+        }
+    }
+}
+
+impl<State, Param, Output: Copy + Default> Measure for Measurement<'_, State, Param, Output> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn kind(&self) -> MeasurementKind {
+        self.kind
+    }
+
+    fn size_limit(&self) -> Option<usize> {
+        self.func.size_limit()
+    }
+
+    fn min_size(&self) -> Option<usize> {
+        self.func.min_size()
+    }
+
+    fn density(&self) -> Option<f64> {
+        self.func.density()
+    }
+
+    fn initialize_measurement(&mut self, size: usize) {
+        self.samples_since_build = 0;
+        self.results = Vec::new();
         self.samples.clear();
+        if let Some(histogram) = self.histogram.as_mut() {
+            *histogram = Histogram::new();
+        }
+        if let Some(counters) = self.counters.as_mut() {
+            counters.clear();
+        }
         self.size = size;
+        self.params_rng = params_rng(self.seed, size);
         self.repetitions = 0;
+        self.drop_stale_context();
+    }
+
+    fn release_context(&mut self) {
+        *self.context.borrow_mut() = None;
+        self.samples_since_build = 0;
+    }
+
+    fn required_memory(&mut self, size: usize) -> usize {
+        let bytes_per_element = match self.bytes_per_element.get() {
+            Some(bytes_per_element) => bytes_per_element,
+            None => {
+                let bytes_per_element = self.func.bytes_per_element().unwrap_or_else(|| self.estimate_bytes_per_element());
+                self.bytes_per_element.set(Some(bytes_per_element));
+                bytes_per_element
+            }
+        };
+
+        let context = (bytes_per_element * size as f64).ceil() as usize;
+        match self.kind {
+            // construction keeps the input and context of every repetition of a sample alive at once.
+            // Until the repetitions are estimated for this size, those of the previous, smaller size
+            // are an upper bound.
+            MeasurementKind::Build => context.saturating_mul(self.repetitions.max(1) as usize),
+            _ => context,
+        }
     }
 
-    pub(crate) fn estimate_timing(&mut self) {
+    fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    fn estimate_timing(&mut self) {
         if self.size == 0 {
             eprintln!("Please call initialize_measurement(size) before starting timing.");
             return;
@@ -43,16 +539,9 @@ impl<'a, State, Param> Measurement<'a, State, Param> {
         let mut repetitions = 1;
 
         loop {
-            let state = self.func.create_context(self.size);
-            let params = self.func.prepare_params(repetitions as usize, self.size);
-
-            let start = Instant::now();
-            for _ in 0..repetitions {
-                self.func.execute(&state, &params[0]);
-            }
-            timing = start.elapsed().as_nanos();
+            timing = self.run_sample(repetitions);
 
-            if timing > MINIMUM_RUNNING_TIME as u128 {
+            if timing > self.minimum_running_time || self.past_deadline() {
                 break;
             } else {
                 repetitions *= 2;
@@ -60,58 +549,99 @@ impl<'a, State, Param> Measurement<'a, State, Param> {
         }
 
         let time_per_call = timing as f64 / repetitions as f64;
-        let reps_per_measurement = (MINIMUM_RUNNING_TIME as f64 / time_per_call).ceil() as u64;
-        println!("{} requires approx. {time_per_call:.2} ns per call and thus we run {reps_per_measurement} repetitions per measurement", self.name);
+        let reps_per_measurement = (self.minimum_running_time as f64 / time_per_call).ceil() as u64;
+        println!("{} ({}) requires approx. {time_per_call:.2} ns per call and thus we run {reps_per_measurement} repetitions per measurement", self.name, self.kind);
 
         self.repetitions = reps_per_measurement;
     }
 
-    pub(crate) fn benchmark_chunk(&mut self) {
+    fn benchmark_chunk(&mut self) {
         if self.repetitions == 0 {
             eprintln!("Please call estimate_timing() before benchmark_chunk()");
             return;
         }
-        let mut samples = Vec::with_capacity(CHUNK_TIME as usize / MINIMUM_RUNNING_TIME as usize);
+        let mut samples = Vec::with_capacity((self.chunk_time / self.minimum_running_time.max(1)) as usize);
 
-        println!("Measuring chunk for {}", self.name);
-        let chunk_start = Instant::now();
+        println!("Measuring chunk for {} ({})", self.name, self.kind);
+        // building the context does not count against the chunk time, so a released context does
+        // not cost any samples
+        self.prepare_context();
+        let mut chunk_start = Instant::now();
         loop {
-            let state = self.func.create_context(self.size);
-            let params = self.func.prepare_params(self.repetitions as usize, self.size);
-
-            let start = Instant::now();
-            for _ in 0..self.repetitions {
-                self.func.execute(&state, &params[0]);
+            if self.rebuild_interval.is_some_and(|interval| self.samples_since_build >= interval) {
+                let rebuild_start = Instant::now();
+                self.release_context();
+                self.prepare_context();
+                chunk_start += rebuild_start.elapsed();
             }
-            samples.push(start.elapsed().as_nanos() as u64);
 
-            if chunk_start.elapsed().as_nanos() > CHUNK_TIME as u128 {
+            samples.push(self.run_sample(self.repetitions));
+            self.samples_since_build += 1;
+
+            if chunk_start.elapsed().as_nanos() > self.chunk_time as u128 || self.past_deadline() {
                 break;
             }
         }
         self.samples.extend(samples);
+        self.record_histogram();
 
-        let (mean, _, relative_std_deviation, _, _) = self.get_final_measurement();
+        let statistics = self.get_final_measurement();
 
-        println!("Median: {mean:.2} ns +- {:.4}%", relative_std_deviation * 100.0);
+        println!("Mean: {:.2} ns +- {:.4}%", statistics.mean, statistics.relative_std_dev() * 100.0);
     }
 
-    /// Get the mean, standard deviation,
-    /// relative standard deviation (i.e., standard deviation divided by median),
-    /// min, and max of all samples that have been measured so far.
-    ///
-    /// The function requires a mutable reference because it sorts the measurements in-place.
-    pub(crate) fn get_final_measurement(&mut self) -> (f64, f64, f64, f64, f64) {
-        self.samples.sort_unstable();
-
-        // technically not the median as defined by literature, but since this is the median of means
-        // anyway, I am not too concerned.
-        let median = self.samples[self.samples.len() / 2] as f64 / self.repetitions as f64;
-        let std_deviation = (self.samples.iter().map(|&x| ((x as f64 / self.repetitions as f64) - median).powf(2.0)).sum::<f64>() / (self.samples.len() - 1) as f64).sqrt();
-        let relative_std_deviation = std_deviation / median;
-        let min = self.samples[0] as f64 / self.repetitions as f64;
-        let max = self.samples[self.samples.len() - 1] as f64 / self.repetitions as f64;
-
-        (median, std_deviation, relative_std_deviation, min, max)
+    fn histogram(&self) -> Option<&Histogram> {
+        self.histogram.as_ref()
+    }
+
+    fn counters(&self) -> Option<[Option<f64>; EVENTS]> {
+        self.counters.as_ref().map(PerfCounters::per_query)
+    }
+
+    fn samples(&self) -> &[u64] {
+        &self.samples
+    }
+
+    fn repetitions(&self) -> u64 {
+        self.repetitions * self.elements_per_query()
+    }
+
+    fn get_final_measurement(&self) -> Statistics {
+        Statistics::from_raw_samples(&self.samples, self.repetitions())
+    }
+}
+
+#[inline]
+fn start_counters(counters: &mut Option<PerfCounters>) {
+    if let Some(counters) = counters {
+        counters.start();
+    }
+}
+
+#[inline]
+fn stop_counters(counters: &mut Option<PerfCounters>, queries: u64) {
+    if let Some(counters) = counters {
+        counters.stop(queries);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_stride_is_rejected() {
+        assert!("strided:0".parse::<AccessPattern>().is_err());
+        assert!(matches!("strided:3".parse(), Ok(AccessPattern::Strided(3))));
+    }
+
+    #[test]
+    fn stride_multiple_of_length_does_not_repeat_positions() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for stride in [8, 16, 8 * 1000] {
+            let mut positions = AccessPattern::Strided(stride).positions(8, 8, &mut rng).into_vec();
+            positions.sort_unstable();
+            assert_eq!(positions, (0..8).collect::<Vec<_>>(), "stride {stride}");
+        }
     }
 }
@@ -0,0 +1,186 @@
+//! Provenance of a run: the machine, the build, and the configuration of the harness. It is written as
+//! a JSON sidecar next to the measurements, so every result file can be traced back to its environment.
+
+use crate::alloc::total_memory;
+use crate::benchmark::MAX_RETAINED_SIZE;
+use crate::histogram::SUB_BUCKET_BITS;
+use crate::measure::ESTIMATION_SIZE;
+use crate::perf::EVENT_NAMES;
+use crate::settings::Settings;
+use crate::sink::{OutputPolicy, COLUMNS, HISTOGRAM_COLUMNS, MEMORY_COLUMNS, SAMPLE_COLUMNS, SCHEMA_VERSION};
+use crate::statistics::{BOOTSTRAP_RESAMPLES, CONFIDENCE_LEVEL};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread::available_parallelism;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The stem of the metadata file in the output directory.
+const METADATA_STEM: &str = "metadata";
+
+/// The name of the metadata file of the current run. Unless earlier runs are overwritten, every run
+/// has its own metadata file named after the run id, so that rows of earlier runs keep referencing
+/// their own metadata.
+pub(crate) fn file_name(settings: &Settings) -> String {
+    match settings.output_policy {
+        OutputPolicy::Overwrite => format!("{}.json", METADATA_STEM),
+        OutputPolicy::Append | OutputPolicy::Timestamped => format!("{}_{}.json", METADATA_STEM, settings.run_id),
+    }
+}
+
+/// Write the metadata of the current run into the output directory.
+pub(crate) fn write(output_dir: &Path, settings: &Settings, suites: &[&str]) -> io::Result<()> {
+    let metadata = collect(settings, suites);
+    let json = serde_json::to_string_pretty(&metadata).map_err(io::Error::other)?;
+    fs::write(output_dir.join(file_name(settings)), json)
+}
+
+fn collect(settings: &Settings, suites: &[&str]) -> Value {
+    let started_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+
+    json!({
+        "run_id": settings.run_id,
+        "started_at": started_at,
+        "command_line": std::env::args().collect::<Vec<_>>(),
+        "suites": suites,
+        "system": system(),
+        "build": build(),
+        "crates": crates(),
+        "settings": {
+            "libraries": settings.libraries.iter().map(|pattern| pattern.as_str()).collect::<Vec<_>>(),
+            "access_pattern": format!("{:?}", settings.access_pattern),
+            "rebuild_interval": settings.rebuild_interval,
+            "measure_throughput": settings.measure_throughput,
+            "measure_latency": settings.measure_latency,
+            "measure_construction": settings.measure_construction,
+            "latency_histograms": settings.latency_histograms,
+            "perf_counters": settings.perf_counters,
+            "raw_samples": settings.raw_samples,
+            "seed": settings.seed,
+            "densities": settings.densities,
+            "min_log_size": settings.min_log_size,
+            "max_log_size": settings.max_log_size,
+            "log_size_step": settings.log_size_step,
+            "min_running_time_ms": settings.min_running_time,
+            "chunk_time_ms": settings.chunk_time,
+            "rounds": settings.rounds,
+            "memory_budget_bytes": settings.memory_budget_bytes(),
+            "runner_timeout_s": settings.runner_timeout,
+            "output_policy": format!("{:?}", settings.output_policy).to_lowercase(),
+            "formats": settings.formats.iter().map(|format| format!("{:?}", format).to_lowercase()).collect::<Vec<_>>(),
+        },
+        "schema": {
+            "version": SCHEMA_VERSION,
+            "columns": COLUMNS,
+            "sample_columns": SAMPLE_COLUMNS,
+            "histogram_columns": HISTOGRAM_COLUMNS,
+            "memory_columns": MEMORY_COLUMNS,
+        },
+        "constants": {
+            "max_retained_size": MAX_RETAINED_SIZE,
+            "memory_estimation_size": ESTIMATION_SIZE,
+            "bootstrap_resamples": BOOTSTRAP_RESAMPLES,
+            "confidence_level": CONFIDENCE_LEVEL,
+            "histogram_sub_bucket_bits": SUB_BUCKET_BITS,
+            "perf_events": EVENT_NAMES,
+        },
+    })
+}
+
+fn system() -> Value {
+    let affinity = affinity();
+    // the governors of the CPUs the harness may run on, or of all CPUs if the affinity is unknown
+    let governors = (0..available_cpus())
+        .filter(|cpu| affinity.as_ref().is_none_or(|affinity| affinity.contains(cpu)))
+        .filter_map(|cpu| {
+            let path = format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq/scaling_governor");
+            Some((cpu.to_string(), Value::from(fs::read_to_string(path).ok()?.trim())))
+        })
+        .collect::<Map<_, _>>();
+
+    json!({
+        "cpu_model": cpu_model(),
+        "available_parallelism": available_parallelism().map(|n| n.get()).ok(),
+        "affinity": affinity,
+        "governors": governors,
+        "kernel": fs::read_to_string("/proc/sys/kernel/osrelease").ok().map(|release| release.trim().to_string()),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "memory_bytes": total_memory(),
+        "perf_event_paranoid": fs::read_to_string("/proc/sys/kernel/perf_event_paranoid").ok().map(|level| level.trim().to_string()),
+    })
+}
+
+fn build() -> Value {
+    json!({
+        "harness_version": env!("CARGO_PKG_VERSION"),
+        "rustc": env!("VERS_BENCH_RUSTC_VERSION"),
+        "rustflags": env!("VERS_BENCH_RUSTFLAGS"),
+        "profile": env!("VERS_BENCH_PROFILE"),
+        "target": env!("VERS_BENCH_TARGET"),
+        "target_features": env!("VERS_BENCH_TARGET_FEATURES").split(',').filter(|feature| !feature.is_empty()).collect::<Vec<_>>(),
+        "vers_commit": env!("VERS_BENCH_VERS_COMMIT"),
+        "vers_dependency": env!("VERS_BENCH_VERS_DEPENDENCY"),
+    })
+}
+
+/// The locked versions of the compared crates, recorded by the build script.
+fn crates() -> Value {
+    let mut crates = Map::new();
+    for entry in env!("VERS_BENCH_CRATE_VERSIONS").split(';').filter(|entry| !entry.is_empty()) {
+        if let Some((name, version)) = entry.split_once('=') {
+            match crates.get_mut(name) {
+                Some(Value::String(versions)) => *versions = format!("{versions}, {version}"),
+                _ => {
+                    crates.insert(name.to_string(), Value::from(version));
+                }
+            }
+        }
+    }
+    Value::Object(crates)
+}
+
+fn cpu_model() -> Option<String> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    cpuinfo
+        .lines()
+        .find(|line| line.starts_with("model name") || line.starts_with("Model"))
+        .and_then(|line| line.split_once(':'))
+        .map(|(_, model)| model.trim().to_string())
+}
+
+/// The number of CPUs configured in the system, which may exceed the CPUs the harness may run on.
+fn available_cpus() -> usize {
+    fs::read_dir("/sys/devices/system/cpu")
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    name.strip_prefix("cpu").is_some_and(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+                })
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// The CPUs the harness is pinned to.
+#[cfg(target_os = "linux")]
+fn affinity() -> Option<Vec<usize>> {
+    // SAFETY: the set is zero-initialized and only accessed through the libc macros
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return None;
+        }
+        Some((0..libc::CPU_SETSIZE as usize).filter(|&cpu| libc::CPU_ISSET(cpu, &set)).collect())
+    }
+}
+
+/// The CPUs the harness is pinned to, which is unknown on this operating system.
+#[cfg(not(target_os = "linux"))]
+fn affinity() -> Option<Vec<usize>> {
+    None
+}
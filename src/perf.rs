@@ -0,0 +1,266 @@
+//! Hardware performance counters read with `perf_event_open`, normalized per query. Counters are only
+//! available on Linux. If the kernel refuses to open them (for example because of
+//! `kernel.perf_event_paranoid`, or in a virtual machine without a PMU), no counters are collected.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The number of collected events.
+pub(crate) const EVENTS: usize = 6;
+
+/// The names of the collected events, in the order of their columns in the output.
+pub(crate) const EVENT_NAMES: [&str; EVENTS] = ["instructions", "cycles", "branch_misses", "cache_misses", "l1d_misses", "dtlb_misses"];
+
+/// Whether the warning about unavailable counters was already printed.
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// A group of hardware counters for the current thread, which accumulates the events of all
+/// samples it was started and stopped around.
+pub(crate) struct PerfCounters {
+    group: sys::Group,
+    totals: [u64; EVENTS],
+    queries: u64,
+}
+
+impl PerfCounters {
+    /// Open the counters. Returns `None` if the kernel does not allow counting any event, in which
+    /// case a warning is printed once.
+    pub(crate) fn open() -> Option<Self> {
+        match sys::Group::open() {
+            Ok(group) => Some(Self { group, totals: [0; EVENTS], queries: 0 }),
+            Err(e) => {
+                if !WARNED.swap(true, Ordering::Relaxed) {
+                    eprintln!("WARNING: hardware performance counters are unavailable, continuing without them: {}", e);
+                }
+                None
+            }
+        }
+    }
+
+    /// Reset and start counting.
+    #[inline]
+    pub(crate) fn start(&mut self) {
+        self.group.reset_and_enable();
+    }
+
+    /// Stop counting, and add the events counted since [`start`](PerfCounters::start) to the totals
+    /// of a sample with `queries` queries.
+    #[inline]
+    pub(crate) fn stop(&mut self, queries: u64) {
+        self.group.disable();
+        if let Some(counts) = self.group.read() {
+            for (total, count) in self.totals.iter_mut().zip(counts) {
+                *total += count;
+            }
+            self.queries += queries;
+        }
+    }
+
+    /// Discard all accumulated events.
+    pub(crate) fn clear(&mut self) {
+        self.totals = [0; EVENTS];
+        self.queries = 0;
+    }
+
+    /// The average number of each event per query, or `None` for events the kernel did not count.
+    pub(crate) fn per_query(&self) -> [Option<f64>; EVENTS] {
+        let mut per_query = [None; EVENTS];
+        if self.queries > 0 {
+            for (event, value) in per_query.iter_mut().enumerate() {
+                if self.group.counts(event) {
+                    *value = Some(self.totals[event] as f64 / self.queries as f64);
+                }
+            }
+        }
+        per_query
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use super::EVENTS;
+    use std::fs::File;
+    use std::io::{Error, Read};
+    use std::os::fd::{AsRawFd, FromRawFd};
+
+    const TYPE_HARDWARE: u32 = 0;
+    const TYPE_HW_CACHE: u32 = 3;
+
+    const HW_CPU_CYCLES: u64 = 0;
+    const HW_INSTRUCTIONS: u64 = 1;
+    const HW_CACHE_MISSES: u64 = 3;
+    const HW_BRANCH_MISSES: u64 = 5;
+
+    const CACHE_L1D: u64 = 0;
+    const CACHE_DTLB: u64 = 3;
+    const CACHE_OP_READ: u64 = 0;
+    const CACHE_RESULT_MISS: u64 = 1;
+
+    /// The events in the order of [`EVENT_NAMES`](super::EVENT_NAMES).
+    const EVENT_CONFIGS: [(u32, u64); EVENTS] = [
+        (TYPE_HARDWARE, HW_INSTRUCTIONS),
+        (TYPE_HARDWARE, HW_CPU_CYCLES),
+        (TYPE_HARDWARE, HW_BRANCH_MISSES),
+        (TYPE_HARDWARE, HW_CACHE_MISSES),
+        (TYPE_HW_CACHE, CACHE_L1D | CACHE_OP_READ << 8 | CACHE_RESULT_MISS << 16),
+        (TYPE_HW_CACHE, CACHE_DTLB | CACHE_OP_READ << 8 | CACHE_RESULT_MISS << 16),
+    ];
+
+    const FLAG_DISABLED: u64 = 1 << 0;
+    const FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+    const FLAG_EXCLUDE_HV: u64 = 1 << 6;
+
+    const FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+    const FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+    const FORMAT_GROUP: u64 = 1 << 3;
+
+    const FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+    const IOC_ENABLE: libc::c_ulong = 0x2400;
+    const IOC_DISABLE: libc::c_ulong = 0x2401;
+    const IOC_RESET: libc::c_ulong = 0x2403;
+    const IOC_FLAG_GROUP: libc::c_ulong = 1;
+
+    /// The first version of `struct perf_event_attr`, which every kernel accepts.
+    #[repr(C)]
+    #[derive(Default)]
+    struct PerfEventAttr {
+        kind: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        wakeup_events: u32,
+        bp_type: u32,
+        config1: u64,
+    }
+
+    /// A counter group. The first event that could be opened leads the group, so all events are
+    /// scheduled onto the PMU together.
+    pub(super) struct Group {
+        leader: File,
+        _members: Vec<File>,
+        /// The event index of each counter in the group, in the order the kernel reports them.
+        events: Vec<usize>,
+    }
+
+    impl Group {
+        pub(super) fn open() -> Result<Self, Error> {
+            let mut leader: Option<File> = None;
+            let mut members = Vec::new();
+            let mut events = Vec::new();
+            let mut last_error = None;
+
+            for (event, &(kind, config)) in EVENT_CONFIGS.iter().enumerate() {
+                let group_fd = leader.as_ref().map_or(-1, |leader| leader.as_raw_fd());
+                match open_event(kind, config, group_fd) {
+                    Ok(file) => {
+                        if leader.is_none() {
+                            leader = Some(file);
+                        } else {
+                            members.push(file);
+                        }
+                        events.push(event);
+                    }
+                    Err(e) => last_error = Some(e),
+                }
+            }
+
+            match leader {
+                Some(leader) => Ok(Self { leader, _members: members, events }),
+                None => Err(last_error.unwrap_or_else(|| Error::other("no events configured"))),
+            }
+        }
+
+        pub(super) fn reset_and_enable(&self) {
+            // SAFETY: the ioctls only act on the counters of the open file descriptor
+            unsafe {
+                libc::ioctl(self.leader.as_raw_fd(), IOC_RESET as _, IOC_FLAG_GROUP);
+                libc::ioctl(self.leader.as_raw_fd(), IOC_ENABLE as _, IOC_FLAG_GROUP);
+            }
+        }
+
+        pub(super) fn disable(&self) {
+            // SAFETY: the ioctl only acts on the counters of the open file descriptor
+            unsafe {
+                libc::ioctl(self.leader.as_raw_fd(), IOC_DISABLE as _, IOC_FLAG_GROUP);
+            }
+        }
+
+        /// Whether the event with the given index is counted by the group.
+        pub(super) fn counts(&self, event: usize) -> bool {
+            self.events.contains(&event)
+        }
+
+        /// Read the counts of all events, scaled up if the kernel multiplexed the group. Returns `None`
+        /// if the group was never scheduled onto the PMU.
+        pub(super) fn read(&self) -> Option<[u64; EVENTS]> {
+            // the kernel reports the number of counters, the enabled and running times, and the counts
+            let mut buffer = [0u8; (3 + EVENTS) * 8];
+            let length = (&self.leader).read(&mut buffer).ok()?;
+            let values = buffer[..length]
+                .chunks_exact(8)
+                .map(|chunk| u64::from_ne_bytes(chunk.try_into().unwrap()))
+                .collect::<Vec<_>>();
+
+            let (enabled, running) = (values[1], values[2]);
+            if running == 0 {
+                return None;
+            }
+
+            let mut counts = [0; EVENTS];
+            for (&event, &value) in self.events.iter().zip(&values[3..]) {
+                counts[event] = (value as u128 * enabled as u128 / running as u128) as u64;
+            }
+            Some(counts)
+        }
+    }
+
+    fn open_event(kind: u32, config: u64, group_fd: i32) -> Result<File, Error> {
+        let attr = PerfEventAttr {
+            kind,
+            size: std::mem::size_of::<PerfEventAttr>() as u32,
+            config,
+            read_format: FORMAT_GROUP | FORMAT_TOTAL_TIME_ENABLED | FORMAT_TOTAL_TIME_RUNNING,
+            flags: FLAG_DISABLED | FLAG_EXCLUDE_KERNEL | FLAG_EXCLUDE_HV,
+            ..Default::default()
+        };
+
+        // SAFETY: the attribute struct outlives the call, and its size field matches its layout
+        let fd = unsafe { libc::syscall(libc::SYS_perf_event_open, &attr as *const PerfEventAttr, 0, -1, group_fd, FLAG_FD_CLOEXEC) };
+        if fd < 0 {
+            Err(Error::last_os_error())
+        } else {
+            // SAFETY: the kernel returned a new file descriptor that nothing else owns
+            Ok(unsafe { File::from_raw_fd(fd as i32) })
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use super::EVENTS;
+    use std::io::Error;
+
+    /// Counters are not supported on this operating system, so a group can never be opened.
+    pub(super) struct Group;
+
+    impl Group {
+        pub(super) fn open() -> Result<Self, Error> {
+            Err(Error::other("performance counters are only supported on Linux"))
+        }
+
+        pub(super) fn reset_and_enable(&self) {}
+
+        pub(super) fn disable(&self) {}
+
+        pub(super) fn counts(&self, _event: usize) -> bool {
+            false
+        }
+
+        pub(super) fn read(&self) -> Option<[u64; EVENTS]> {
+            None
+        }
+    }
+}
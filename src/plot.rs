@@ -0,0 +1,223 @@
+//! Rendering of result files to SVG plots. Every measurement kind of a result file is plotted with
+//! one line per runner over the sizes on a logarithmic axis. If the runners are too far apart to be
+//! compared in one plot, they are split into a plot of the fast and a plot of the slow runners.
+//! Results of a density sweep get plots of their own for every density.
+
+use crate::cli::PlotArgs;
+use crate::results::{self, ResultRecord};
+use clap::ValueEnum;
+use plotters::prelude::*;
+use std::error::Error;
+use std::path::Path;
+
+/// The default ratio between the slowest and the fastest runner above which they are split into
+/// separate plots.
+pub(crate) const DEFAULT_SPLIT_RATIO: f64 = 4.0;
+
+/// The size of every plot in pixels.
+const PLOT_SIZE: (u32, u32) = (1024, 640);
+
+/// The range shown by the error bars of every point.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ErrorBars {
+    /// The bootstrap confidence interval of the mean.
+    Ci,
+
+    /// The fastest and the slowest sample.
+    MinMax,
+
+    /// No error bars.
+    None,
+}
+
+/// The mean of a runner at one size, with the range of its error bar.
+#[derive(Clone)]
+struct Point {
+    log_size: f64,
+    mean: f64,
+    low: f64,
+    high: f64,
+}
+
+/// The points of one runner. The color is assigned before splitting, so a runner has the same color
+/// in all plots of a kind.
+#[derive(Clone)]
+struct Series {
+    runner: String,
+    color: usize,
+    points: Vec<Point>,
+}
+
+impl Series {
+    /// The geometric mean over all sizes, which weighs every size equally regardless of its magnitude.
+    fn score(&self) -> f64 {
+        let positive = self.points.iter().filter(|point| point.mean > 0.0).map(|point| point.mean.ln()).collect::<Vec<_>>();
+        if positive.is_empty() {
+            return 0.0;
+        }
+        (positive.iter().sum::<f64>() / positive.len() as f64).exp()
+    }
+}
+
+/// Plot all result files given on the command line.
+pub(crate) fn plot(args: &PlotArgs) -> Result<(), String> {
+    for input in &args.inputs {
+        let records = results::read(input).map_err(|e| format!("Could not read {}: {}", input.display(), e))?;
+
+        // only plot a single run, by default the last one in the file
+        let run_id = args.run_id.clone().or_else(|| records.last().map(|record| record.text("run_id").to_string())).unwrap_or_default();
+        let records = records
+            .into_iter()
+            .filter(|record| record.text("run_id") == run_id && record.text("status") == "ok")
+            .collect::<Vec<_>>();
+
+        let Some(benchmark) = records.first().map(|record| record.text("benchmark").to_string()) else {
+            println!("{} contains no measurements to plot", input.display());
+            continue;
+        };
+        let output_dir = args.output_dir.as_deref().unwrap_or_else(|| input.parent().unwrap_or(Path::new(".")));
+        let stem = input.file_stem().map_or_else(|| benchmark.to_lowercase(), |stem| stem.to_string_lossy().into_owned());
+
+        // every density of a density sweep is plotted separately, rows without a density form one group
+        let mut densities = Vec::new();
+        for record in &records {
+            if !densities.contains(&record.text("density")) {
+                densities.push(record.text("density"));
+            }
+        }
+
+        for density in densities {
+            let (stem, benchmark) = match density {
+                "" => (stem.clone(), benchmark.clone()),
+                density => (format!("{}_density_{}", stem, density), format!("{}, density {}", benchmark, density)),
+            };
+
+            for kind in &args.kinds {
+                let series = collect_series(&records, kind, density, args.error_bars);
+                if series.is_empty() {
+                    continue;
+                }
+
+                let y_label = if kind == "build" { "Time per construction (ns)" } else { "Time per query (ns)" };
+                for (part, group) in split(series, args.split_ratio, &args.reference) {
+                    let (path, title) = match part {
+                        Some(part) => (output_dir.join(format!("{}_{}_{}.svg", stem, kind, part)), format!("{} ({}, {})", benchmark, kind, part)),
+                        None => (output_dir.join(format!("{}_{}.svg", stem, kind)), format!("{} ({})", benchmark, kind)),
+                    };
+                    draw(&path, &title, y_label, &group).map_err(|e| format!("Could not plot {}: {}", path.display(), e))?;
+                    println!("Plotted {}", path.display());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Collect the series of all runners measured with the given kind and density, in the order they first
+/// appear.
+fn collect_series(records: &[ResultRecord], kind: &str, density: &str, error_bars: ErrorBars) -> Vec<Series> {
+    let mut series: Vec<Series> = Vec::new();
+
+    for record in records.iter().filter(|record| record.text("kind") == kind && record.text("density") == density) {
+        let (Some(size), Some(mean)) = (record.number("size"), record.number("mean_ns")) else {
+            continue;
+        };
+        let (low, high) = match error_bars {
+            ErrorBars::Ci => (record.number("ci_lower_ns"), record.number("ci_upper_ns")),
+            ErrorBars::MinMax => (record.number("min_ns"), record.number("max_ns")),
+            ErrorBars::None => (None, None),
+        };
+        let point = Point { log_size: size.log2(), mean, low: low.unwrap_or(mean), high: high.unwrap_or(mean) };
+
+        let runner = record.text("runner");
+        match series.iter_mut().find(|series| series.runner == runner) {
+            Some(series) => series.points.push(point),
+            None => {
+                let color = series.len();
+                series.push(Series { runner: runner.to_string(), color, points: vec![point] });
+            }
+        }
+    }
+
+    for series in &mut series {
+        series.points.sort_by(|a, b| a.log_size.total_cmp(&b.log_size));
+    }
+    series
+}
+
+/// Split the runners into fast and slow runners at the largest gap between their scores, if the
+/// slowest runner is more than `ratio` times slower than the fastest. The reference runner is shown
+/// in both plots, so they can be related to each other.
+fn split(series: Vec<Series>, ratio: f64, reference: &str) -> Vec<(Option<&'static str>, Vec<Series>)> {
+    let mut scores = series.iter().map(Series::score).filter(|&score| score > 0.0).collect::<Vec<_>>();
+    scores.sort_by(f64::total_cmp);
+
+    let (Some(&fastest), Some(&slowest)) = (scores.first(), scores.last()) else {
+        return vec![(None, series)];
+    };
+    if slowest <= fastest * ratio {
+        return vec![(None, series)];
+    }
+
+    // the fast runners are those up to the largest gap between consecutive scores on a log scale
+    let threshold = scores
+        .windows(2)
+        .max_by(|a, b| (a[1] / a[0]).total_cmp(&(b[1] / b[0])))
+        .map_or(fastest, |window| window[0]);
+
+    let (mut fast, mut slow): (Vec<_>, Vec<_>) = series.into_iter().partition(|series| series.score() <= threshold);
+    if let Some(index) = fast.iter().position(|series| series.runner == reference) {
+        slow.insert(0, fast[index].clone());
+    } else if let Some(index) = slow.iter().position(|series| series.runner == reference) {
+        fast.insert(0, slow[index].clone());
+    }
+
+    vec![(Some("fastest"), fast), (Some("slowest"), slow)]
+}
+
+/// Draw the series into an SVG file at `path`.
+fn draw(path: &Path, title: &str, y_label: &str, series: &[Series]) -> Result<(), Box<dyn Error>> {
+    let points = || series.iter().flat_map(|series| &series.points);
+    let min_log_size = points().map(|point| point.log_size).fold(f64::INFINITY, f64::min).floor();
+    let max_log_size = points().map(|point| point.log_size).fold(f64::NEG_INFINITY, f64::max).ceil();
+    let max_time = points().map(|point| point.high.max(point.mean)).fold(0.0, f64::max) * 1.05;
+
+    let root = SVGBackend::new(path, PLOT_SIZE).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", 24))
+        .margin(16)
+        .set_label_area_size(LabelAreaPosition::Left, 64)
+        .set_label_area_size(LabelAreaPosition::Bottom, 48)
+        .build_cartesian_2d(min_log_size..max_log_size.max(min_log_size + 1.0), 0.0..max_time.max(f64::MIN_POSITIVE))?;
+
+    // the sizes are plotted by their logarithm, and labelled with the powers of two they represent
+    chart
+        .configure_mesh()
+        .x_desc("Elements")
+        .y_desc(y_label)
+        .x_labels((max_log_size - min_log_size) as usize + 1)
+        .x_label_formatter(&|log_size| format!("2^{}", log_size.round()))
+        .draw()?;
+
+    for series in series {
+        let color = Palette99::pick(series.color).to_rgba();
+
+        chart
+            .draw_series(LineSeries::new(series.points.iter().map(|point| (point.log_size, point.mean)), color.stroke_width(2)))?
+            .label(&series.runner)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2)));
+        chart.draw_series(series.points.iter().map(|point| ErrorBar::new_vertical(point.log_size, point.low, point.mean, point.high, color.filled(), 6)))?;
+    }
+
+    chart
+        .configure_series_labels()
+        .border_style(BLACK)
+        .background_style(WHITE.mix(0.8))
+        .position(SeriesLabelPosition::UpperLeft)
+        .draw()?;
+
+    root.present()?;
+    Ok(())
+}
@@ -0,0 +1,185 @@
+//! Reading result files written by the [sinks](crate::sink). Columns are looked up by name, so files
+//! written by older versions of the harness with fewer columns can still be read.
+
+use crate::statistics::Statistics;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// One row of a result file, mapping column names to their raw values. Missing values are empty.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ResultRecord {
+    values: HashMap<String, String>,
+}
+
+impl ResultRecord {
+    /// The value of the column, or an empty string if the row has no value for it.
+    pub(crate) fn text(&self, column: &str) -> &str {
+        self.values.get(column).map_or("", String::as_str)
+    }
+
+    /// The numeric value of the column, if it has one.
+    pub(crate) fn number(&self, column: &str) -> Option<f64> {
+        self.text(column).parse().ok()
+    }
+
+    /// Recompute the statistics of a row of a raw sample file from its samples.
+    pub(crate) fn statistics_from_samples(&self) -> Option<Statistics> {
+        let repetitions = self.number("repetitions")? as u64;
+        let samples = self.text("samples_ns").split_whitespace().map(str::parse).collect::<Result<Vec<u64>, _>>().ok()?;
+        (repetitions > 0 && !samples.is_empty()).then(|| Statistics::from_raw_samples(&samples, repetitions))
+    }
+
+    /// The statistics of a measured row, or `None` for marker rows of runners that were not measured.
+    pub(crate) fn statistics(&self) -> Option<Statistics> {
+        Some(Statistics {
+            samples: self.number("samples")? as usize,
+            mean: self.number("mean_ns")?,
+            median: self.number("median_ns")?,
+            std_dev: self.number("std_dev_ns")?,
+            mad: self.number("mad_ns")?,
+            min: self.number("min_ns")?,
+            max: self.number("max_ns")?,
+            p1: self.number("p1_ns")?,
+            p5: self.number("p5_ns")?,
+            p95: self.number("p95_ns")?,
+            p99: self.number("p99_ns")?,
+            ci_lower: self.number("ci_lower_ns")?,
+            ci_upper: self.number("ci_upper_ns")?,
+            mild_outliers: self.number("mild_outliers")? as usize,
+            severe_outliers: self.number("severe_outliers")? as usize,
+        })
+    }
+}
+
+/// Read all rows of a CSV file with a header row, or of a JSON-lines file if its extension is `jsonl`.
+pub(crate) fn read(path: &Path) -> io::Result<Vec<ResultRecord>> {
+    let content = fs::read_to_string(path)?;
+    if is_json_lines(path) {
+        read_json_lines(&content)
+    } else {
+        read_csv(&content)
+    }
+}
+
+/// Read the column names of a file from its first line, which is the header row of a CSV file, or the
+/// first object of a JSON-lines file. Empty files have no columns.
+pub(crate) fn columns(path: &Path) -> io::Result<Vec<String>> {
+    let mut line = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut line)?;
+    let line = line.trim_end();
+    if line.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if is_json_lines(path) {
+        match serde_json::from_str(line).map_err(io::Error::other)? {
+            Value::Object(object) => Ok(object.into_iter().map(|(column, _)| column).collect()),
+            _ => Err(io::Error::other(format!("expected a JSON object per line, found {}", line))),
+        }
+    } else {
+        Ok(split_csv_line(line))
+    }
+}
+
+fn is_json_lines(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "jsonl")
+}
+
+fn read_csv(content: &str) -> io::Result<Vec<ResultRecord>> {
+    let mut lines = content.lines().filter(|line| !line.is_empty());
+    let header = lines.next().map(split_csv_line).unwrap_or_default();
+
+    Ok(lines
+        .map(|line| ResultRecord { values: header.iter().cloned().zip(split_csv_line(line)).collect() })
+        .collect())
+}
+
+/// Split a CSV line into its fields, removing the quotes around quoted fields.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn read_json_lines(content: &str) -> io::Result<Vec<ResultRecord>> {
+    content
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let Value::Object(object) = serde_json::from_str(line).map_err(io::Error::other)? else {
+                return Err(io::Error::other(format!("expected a JSON object per line, found {}", line)));
+            };
+
+            let values = object
+                .into_iter()
+                .map(|(column, value)| {
+                    let value = match value {
+                        Value::Null => String::new(),
+                        Value::String(s) => s,
+                        value => value.to_string(),
+                    };
+                    (column, value)
+                })
+                .collect();
+            Ok(ResultRecord { values })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_plain_fields() {
+        assert_eq!(split_csv_line("a,b,,c"), ["a", "b", "", "c"]);
+        assert_eq!(split_csv_line(""), [""]);
+        assert_eq!(split_csv_line("a,"), ["a", ""]);
+    }
+
+    #[test]
+    fn split_quoted_fields() {
+        assert_eq!(split_csv_line("\"a,b\",c"), ["a,b", "c"]);
+        assert_eq!(split_csv_line("\"say \"\"hi\"\"\",x"), ["say \"hi\"", "x"]);
+        assert_eq!(split_csv_line("\"\",\"\"\"\""), ["", "\""]);
+    }
+
+    #[test]
+    fn csv_rows_are_keyed_by_header() {
+        let records = read_csv("benchmark,runner,size\nRank,\"Vers, fast\",64\n\nRank,Sux\n").unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].text("runner"), "Vers, fast");
+        assert_eq!(records[0].number("size"), Some(64.0));
+        // missing trailing fields are empty
+        assert_eq!(records[1].text("size"), "");
+        assert_eq!(records[1].number("size"), None);
+    }
+
+    #[test]
+    fn json_lines_render_null_as_empty() {
+        let records = read_json_lines("{\"runner\":\"Vers\",\"size\":64,\"density\":null}\n").unwrap();
+        assert_eq!(records[0].text("runner"), "Vers");
+        assert_eq!(records[0].text("size"), "64");
+        assert_eq!(records[0].text("density"), "");
+    }
+}
@@ -1,3 +1,6 @@
+use crate::measure::AccessPattern;
+use rand::rngs::StdRng;
+
 /// A runner is a trait that defines a single benchmark function and associated parameter types.
 /// The trait is implemented on the data structure that is to be benchmarked, and the [`execute`] method
 /// takes an additional parameter that is passed to the benchmark function.
@@ -8,34 +11,227 @@ pub(crate) trait Runner {
 
     type Param;
 
-    fn create_context(&self, size: usize) -> Self::Context;
+    /// The result of a single query. Results are passed to [`black_box`](std::hint::black_box) by the
+    /// harness, so the runner does not need to prevent the optimizer from removing the query itself.
+    type Output: Copy + Default;
+
+    /// Create the context of the given size. All randomness of the input must be drawn from `rng`,
+    /// which the harness seeds identically for every runner of the same size, so all libraries are
+    /// benchmarked on the same input.
+    fn create_context(&self, size: usize, rng: &mut StdRng) -> Self::Context;
+
+    /// The approximate peak heap memory in bytes per element needed to create a context, if the runner
+    /// knows it. Otherwise, the harness estimates it by building a small context.
+    fn bytes_per_element(&self) -> Option<f64> {
+        None
+    }
+
+    /// The exclusive upper bound of the sizes the runner can be measured at, if it has one. Larger sizes
+    /// are skipped, e.g. because the data structure does not scale to them.
+    fn size_limit(&self) -> Option<usize> {
+        None
+    }
+
+    /// The inclusive lower bound of the sizes the runner can be measured at, if it has one. Smaller sizes
+    /// are skipped, e.g. because the input cannot be generated for them.
+    fn min_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// How many elements a single query processes at the given size, e.g. the size itself if every
+    /// query is a complete pass. Query times are reported per element, so they do not grow with the
+    /// size just because the queries do more work.
+    fn elements_per_query(&self, _size: usize) -> usize {
+        1
+    }
+
+    /// The fraction of one bits in the input, if the runner is configured with one. It is recorded in
+    /// every result row, so the measurements of a library at different densities can be told apart.
+    fn density(&self) -> Option<f64> {
+        None
+    }
+
+    /// Build `repetitions` contexts of the given size and return the nanoseconds spent building them.
+    /// Generating the input of the data structures and dropping them afterward is not timed.
+    fn measure_construction(&self, size: usize, repetitions: usize, rng: &mut StdRng) -> u64;
+
+    /// Prepare `number` query parameters for a context of the given size, drawing all randomness from `rng`.
+    /// The positions queried by the parameters follow the access `pattern`.
+    fn prepare_params(&self, number: usize, size: usize, pattern: AccessPattern, rng: &mut StdRng) -> Box<[Self::Param]>;
+
+    fn execute(&self, context: &Self::Context, param: &Self::Param) -> Self::Output;
+
+    /// Execute one independent query per parameter and write the results into the corresponding
+    /// entry of `results`, which has the same length as `params`.
+    /// Unlike repeated calls to [`execute`] through a trait object, the batch has no per-call overhead,
+    /// and the CPU can overlap the queries, so this measures throughput rather than latency.
+    ///
+    /// [`execute`]: Runner::execute
+    fn execute_batch(&self, context: &Self::Context, params: &[Self::Param], results: &mut [Self::Output]) {
+        for (param, result) in params.iter().zip(results.iter_mut()) {
+            *result = self.execute(context, param);
+        }
+    }
+
+    /// The runner as a [`ChainRunner`], if it can derive query parameters from previous results.
+    /// Runners implementing [`ChainRunner`] return themselves.
+    fn chain_runner(&self) -> Option<&dyn ChainRunner<Context = Self::Context, Param = Self::Param, Output = Self::Output>> {
+        None
+    }
+}
+
+/// A runner that derives the parameter of each query from the result of the previous one, which allows
+/// measuring the latency of dependent query chains. The runner exposes itself through
+/// [`Runner::chain_runner`].
+pub(crate) trait ChainRunner: Runner {
+    /// Derive the parameter of the next query on `context` from the `output` of the previous one. The
+    /// `step` is the position of the query in the chain, and should be mixed into the parameter to avoid
+    /// short cycles.
+    fn chain(&self, context: &Self::Context, output: Self::Output, step: usize, size: usize) -> Self::Param;
+
+    /// Execute a chain of `length` queries on a context of the given size, where each parameter is
+    /// derived from the previous result using [`chain`], starting with `first`.
+    /// The CPU cannot overlap the queries, so this measures their latency.
+    /// Returns the parameter that would be used for the next query.
+    ///
+    /// [`chain`]: ChainRunner::chain
+    fn execute_chain(&self, context: &Self::Context, first: Self::Param, length: usize, size: usize) -> Self::Param {
+        let mut param = first;
+        for step in 0..length {
+            let output = self.execute(context, &param);
+            param = self.chain(context, output, step, size);
+        }
+        param
+    }
+}
+
+/// Conversion of the result types of the benchmarked libraries into the [`Runner::Output`] shared by
+/// all runners of a benchmark. Missing results are mapped to the maximum value.
+pub(crate) trait IntoOutput<T> {
+    fn into_output(self) -> T;
+}
 
-    fn prepare_params(&self, number: usize, size: usize) -> Box<[Self::Param]>;
+impl IntoOutput<usize> for usize {
+    fn into_output(self) -> usize {
+        self
+    }
+}
 
-    fn execute(&self, context: &Self::Context, param: &Self::Param);
+impl IntoOutput<usize> for u64 {
+    fn into_output(self) -> usize {
+        self as usize
+    }
 }
 
+impl IntoOutput<usize> for Option<usize> {
+    fn into_output(self) -> usize {
+        self.unwrap_or(usize::MAX)
+    }
+}
+
+impl IntoOutput<usize> for Option<u64> {
+    fn into_output(self) -> usize {
+        self.map_or(usize::MAX, |v| v as usize)
+    }
+}
+
+impl<E> IntoOutput<usize> for Result<u64, E> {
+    fn into_output(self) -> usize {
+        self.map_or(usize::MAX, |v| v as usize)
+    }
+}
+
+/// Implement [`Runner`] for a new unit struct `$name`.
+/// The context is created in two steps: `generate_input` creates the raw input for a given size from the seeded rng,
+/// and `build` turns the input into the benchmarked data structure. Only the `build` step is timed
+/// by [`Runner::measure_construction`]. The batched [`Runner::execute_batch`] is derived from `execute`.
+/// The optional `chain` derives the next query parameter from the previous result and the position
+/// in the chain, and implements [`ChainRunner`] to enable latency measurements.
+/// The optional `size_limit`, `min_size` and `elements_per_query` set [`Runner::size_limit`],
+/// [`Runner::min_size`] and [`Runner::elements_per_query`].
 #[macro_export]
 macro_rules! runner {
-    ($name:ident, create_context = |$size:ident| { $($ctx_body:tt)* }, prepare_params = |$number:ident, $size_params:ident| { $($param_body:tt)* }, execute = |$context:ident: $context_type:ty, $param:ident: $param_type:ty| { $($body:tt)* }) => {
+    ($name:ident, generate_input = |$size:ident, $input_rng:ident| { $($input_body:tt)* }, build = |$input:ident: $input_type:ty| { $($build_body:tt)* }, prepare_params = |$number:ident, $size_params:ident, $pattern:ident, $params_rng:ident| { $($param_body:tt)* }, execute = |$context:ident: $context_type:ty, $param:ident: $param_type:ty| -> $output_type:ty { $($body:tt)* }$(, chain = |$output:ident, $step:ident, $chain_size:ident| { $($chain_body:tt)* })?$(, size_limit = $size_limit:expr)?$(, min_size = $min_size:expr)?$(, elements_per_query = |$elements_size:ident| $elements_per_query:expr)?) => {
         pub(crate) struct $name;
 
+        impl $name {
+            fn generate_input(&self, $size: usize, $input_rng: &mut rand::rngs::StdRng) -> $input_type {
+                $($input_body)*
+            }
+
+            fn build(&self, $input: $input_type) -> $context_type {
+                $($build_body)*
+            }
+        }
+
         impl runner::Runner for $name {
             type Context = $context_type;
 
             type Param = $param_type;
 
-            fn create_context(&self, $size: usize) -> Self::Context {
-                $($ctx_body)*
+            type Output = $output_type;
+
+            fn create_context(&self, size: usize, rng: &mut rand::rngs::StdRng) -> Self::Context {
+                self.build(self.generate_input(size, rng))
             }
 
-            fn prepare_params(&self, $number: usize, $size_params: usize) -> Box<[Self::Param]> {
+            fn measure_construction(&self, size: usize, repetitions: usize, rng: &mut rand::rngs::StdRng) -> u64 {
+                let inputs = (0..repetitions).map(|_| self.generate_input(size, rng)).collect::<Vec<_>>();
+                let mut contexts = Vec::with_capacity(repetitions);
+
+                let start = std::time::Instant::now();
+                for input in inputs {
+                    contexts.push(self.build(input));
+                }
+                let elapsed = start.elapsed().as_nanos() as u64;
+
+                drop(contexts);
+                elapsed
+            }
+
+            fn prepare_params(&self, $number: usize, $size_params: usize, $pattern: $crate::measure::AccessPattern, $params_rng: &mut rand::rngs::StdRng) -> Box<[Self::Param]> {
                 $($param_body)*
             }
 
-            fn execute(&self, $context: &Self::Context, $param: &Self::Param) {
+            #[inline]
+            fn execute(&self, $context: &Self::Context, $param: &Self::Param) -> Self::Output {
                 $($body)*
             }
+
+            $crate::runner!(@chain_runner $($output)?);
+
+            $(
+            fn size_limit(&self) -> Option<usize> {
+                Some($size_limit)
+            }
+            )?
+
+            $(
+            fn min_size(&self) -> Option<usize> {
+                Some($min_size)
+            }
+            )?
+
+            $(
+            fn elements_per_query(&self, $elements_size: usize) -> usize {
+                $elements_per_query
+            }
+            )?
         }
-    }
-}
\ No newline at end of file
+
+        $(
+        impl runner::ChainRunner for $name {
+            #[inline]
+            fn chain(&self, _context: &Self::Context, $output: Self::Output, $step: usize, $chain_size: usize) -> Self::Param {
+                $($chain_body)*
+            }
+        }
+        )?
+    };
+    (@chain_runner) => {};
+    (@chain_runner $output:ident) => {
+        fn chain_runner(&self) -> Option<&dyn runner::ChainRunner<Context = Self::Context, Param = Self::Param, Output = Self::Output>> {
+            Some(self)
+        }
+    };
+}
@@ -0,0 +1,291 @@
+use crate::alloc::total_memory;
+use crate::benchmark::NUM_ROUNDS;
+use crate::measure::{AccessPattern, CHUNK_TIME, MINIMUM_RUNNING_TIME};
+use crate::sink::{generate_run_id, Format, OutputPolicy};
+use clap::builder::RangedU64ValueParser;
+use clap::{ArgAction, Args};
+use glob::Pattern;
+use std::time::Duration;
+
+/// Environment variable selecting the [`AccessPattern`] of all query measurements.
+const ACCESS_PATTERN_VAR: &str = "VERS_BENCH_ACCESS_PATTERN";
+
+/// Environment variable setting the number of samples after which a data structure is rebuilt.
+const REBUILD_INTERVAL_VAR: &str = "VERS_BENCH_REBUILD_INTERVAL";
+
+/// Environment variable enabling (`true`) or disabling (`false`) construction measurements.
+const CONSTRUCTION_VAR: &str = "VERS_BENCH_CONSTRUCTION";
+
+/// Environment variable enabling (`true`) or disabling (`false`) batched throughput measurements.
+const THROUGHPUT_VAR: &str = "VERS_BENCH_THROUGHPUT";
+
+/// Environment variable enabling (`true`) or disabling (`false`) dependent-chain latency measurements.
+const LATENCY_VAR: &str = "VERS_BENCH_LATENCY";
+
+/// Environment variable setting the global seed of all generated inputs and query parameters.
+const SEED_VAR: &str = "VERS_BENCH_SEED";
+
+/// Environment variable setting the memory budget in MiB.
+const MEMORY_BUDGET_VAR: &str = "VERS_BENCH_MEMORY_BUDGET";
+
+/// The fraction of the physical memory used as the memory budget if none is configured.
+const DEFAULT_MEMORY_FRACTION: f64 = 0.8;
+
+const BYTES_PER_MIB: usize = 1 << 20;
+
+/// Environment variable setting the per-runner timeout in seconds.
+const RUNNER_TIMEOUT_VAR: &str = "VERS_BENCH_RUNNER_TIMEOUT";
+
+/// The default wall-clock time in seconds a runner may spend at a single size. Currently, 15 minutes.
+const DEFAULT_RUNNER_TIMEOUT: u64 = 15 * 60;
+
+/// Environment variable selecting the comma-separated densities of the rank/select inputs.
+const DENSITY_VAR: &str = "VERS_BENCH_DENSITY";
+
+/// Environment variable enabling (`true`) or disabling (`false`) per-query latency histograms.
+const HISTOGRAMS_VAR: &str = "VERS_BENCH_HISTOGRAMS";
+
+/// Environment variable enabling (`true`) or disabling (`false`) hardware performance counters.
+const PERF_COUNTERS_VAR: &str = "VERS_BENCH_PERF_COUNTERS";
+
+/// Environment variable selecting the comma-separated formats results are written in.
+const FORMAT_VAR: &str = "VERS_BENCH_FORMAT";
+
+/// Environment variable enabling (`true`) or disabling (`false`) writing the raw samples.
+const RAW_SAMPLES_VAR: &str = "VERS_BENCH_RAW_SAMPLES";
+
+/// Environment variable selecting how the output files of a run treat those of earlier runs.
+const OUTPUT_POLICY_VAR: &str = "VERS_BENCH_OUTPUT_POLICY";
+
+/// The seed used if none is configured, so that runs are reproducible by default.
+const DEFAULT_SEED: u64 = 0x7665_7273;
+
+/// The density of the random bit vectors of the rank and select suites if none is configured.
+pub(crate) const DEFAULT_DENSITY: f64 = 0.5;
+
+const NANOS_PER_MILLI: u64 = 1_000_000;
+
+/// The accepted binary logarithms of the minimum and maximum size. Smaller bit vectors cannot hold the
+/// ones of every density, and larger sizes overflow the size computation.
+const LOG_SIZES: std::ops::RangeInclusive<i64> = 6..=40;
+
+/// The accepted durations in milliseconds. Longer durations overflow in nanoseconds.
+const MILLIS: std::ops::RangeInclusive<u64> = 1..=u64::MAX / NANOS_PER_MILLI;
+
+/// Settings shared by all benchmark suites of one run of the harness.
+/// Every setting can be given on the command line, and some can also be set through the environment.
+#[derive(Args, Clone, Debug)]
+pub(crate) struct Settings {
+    /// Only benchmark runners whose name matches one of these glob patterns. By default, all runners
+    /// of the selected suites are benchmarked.
+    #[arg(long = "library", value_name = "GLOB")]
+    pub(crate) libraries: Vec<Pattern>,
+
+    /// How the positions queried by each sample are chosen: ascending (`sequential`), uniformly random
+    /// (`shuffled`), or with a fixed distance (`strided:<stride>`).
+    #[arg(long, env = ACCESS_PATTERN_VAR, default_value = "shuffled")]
+    pub(crate) access_pattern: AccessPattern,
+
+    /// Rebuild each data structure after this many samples. If unset, every structure is built
+    /// once per size.
+    #[arg(long, env = REBUILD_INTERVAL_VAR, value_name = "SAMPLES")]
+    pub(crate) rebuild_interval: Option<usize>,
+
+    /// Additionally measure the throughput of every runner with batched queries.
+    #[arg(long, env = THROUGHPUT_VAR, default_value_t = true, action = ArgAction::Set)]
+    pub(crate) measure_throughput: bool,
+
+    /// Additionally measure the latency of every runner supporting dependent query chains.
+    #[arg(long, env = LATENCY_VAR, default_value_t = true, action = ArgAction::Set)]
+    pub(crate) measure_latency: bool,
+
+    /// Additionally measure the construction time of every runner's data structure.
+    #[arg(long, env = CONSTRUCTION_VAR, default_value_t = true, action = ArgAction::Set)]
+    pub(crate) measure_construction: bool,
+
+    /// Additionally time individual queries with the cycle counter, and write a latency histogram of
+    /// every query measurement to `<suite>_latency.<format>`.
+    #[arg(long, env = HISTOGRAMS_VAR, default_value_t = false, action = ArgAction::Set)]
+    pub(crate) latency_histograms: bool,
+
+    /// Additionally count hardware events (instructions, cycles, branch, cache and TLB misses) per query
+    /// with `perf_event_open`. Only supported on Linux, and skipped if the kernel does not allow it.
+    #[arg(long, env = PERF_COUNTERS_VAR, default_value_t = false, action = ArgAction::Set)]
+    pub(crate) perf_counters: bool,
+
+    /// Additionally write the raw samples and their repetition counts of every runner and size to
+    /// `<suite>_samples.<format>`, so the statistics can be recomputed later with the `analyze` command.
+    #[arg(long, env = RAW_SAMPLES_VAR, default_value_t = false, action = ArgAction::Set)]
+    pub(crate) raw_samples: bool,
+
+    /// The global seed from which all inputs and query parameters are generated. It is recorded
+    /// in every output row, so a run can be reproduced.
+    #[arg(long, env = SEED_VAR, default_value_t = DEFAULT_SEED)]
+    pub(crate) seed: u64,
+
+    /// The fractions of one bits in the random bit vectors of the rank and select suites. Every
+    /// density is measured as its own series at every size, e.g. `--density 0.01,0.5,0.99`.
+    /// The adversarial select suite passes them to its patterns instead of their own defaults.
+    /// Defaults to 0.5.
+    #[arg(long = "density", env = DENSITY_VAR, value_delimiter = ',', value_parser = parse_density)]
+    pub(crate) densities: Vec<f64>,
+
+    /// Only benchmark sizes of at least 2^MIN_LOG_SIZE elements.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(LOG_SIZES))]
+    pub(crate) min_log_size: Option<u32>,
+
+    /// Only benchmark sizes of at most 2^MAX_LOG_SIZE elements.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(LOG_SIZES))]
+    pub(crate) max_log_size: Option<u32>,
+
+    /// Instead of the default sizes of each suite, benchmark every STEP-th power of two between the
+    /// minimum and maximum size.
+    #[arg(long, value_name = "STEP")]
+    pub(crate) log_size_step: Option<u32>,
+
+    /// The minimum running time of a single sample in milliseconds. Queries are repeated until a
+    /// sample takes at least this long.
+    #[arg(long, value_name = "MS", default_value_t = MINIMUM_RUNNING_TIME / NANOS_PER_MILLI, value_parser = clap::value_parser!(u64).range(MILLIS))]
+    pub(crate) min_running_time: u64,
+
+    /// How long each runner is sampled per round in milliseconds.
+    #[arg(long, value_name = "MS", default_value_t = CHUNK_TIME / NANOS_PER_MILLI, value_parser = clap::value_parser!(u64).range(MILLIS))]
+    pub(crate) chunk_time: u64,
+
+    /// How many interleaved rounds of chunks are measured for every size.
+    #[arg(long, default_value_t = NUM_ROUNDS, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub(crate) rounds: usize,
+
+    /// The memory budget in MiB. Sizes for which a runner is estimated to exceed the budget are
+    /// skipped. Defaults to 80% of the physical memory.
+    #[arg(long, env = MEMORY_BUDGET_VAR, value_name = "MIB")]
+    pub(crate) memory_budget: Option<usize>,
+
+    /// The wall-clock time in seconds a runner may spend at a single size, including building its
+    /// data structure. Runners exceeding it are dropped from all remaining sizes.
+    #[arg(long, env = RUNNER_TIMEOUT_VAR, value_name = "SECONDS", default_value_t = DEFAULT_RUNNER_TIMEOUT, value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) runner_timeout: u64,
+
+    /// The formats the results of every suite are written in, as `<suite>.<format>` files.
+    #[arg(long = "format", env = FORMAT_VAR, value_enum, value_delimiter = ',', default_value = "csv")]
+    pub(crate) formats: Vec<Format>,
+
+    /// Whether the output files of earlier runs are overwritten, appended to, or kept by writing
+    /// separate files named after the run id.
+    #[arg(long, env = OUTPUT_POLICY_VAR, value_enum, default_value = "overwrite")]
+    pub(crate) output_policy: OutputPolicy,
+
+    /// The id of this run, written into every output row. Defaults to the UTC start time of the run.
+    #[arg(long, default_value_t = generate_run_id(), hide_default_value = true)]
+    pub(crate) run_id: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            libraries: Vec::new(),
+            access_pattern: AccessPattern::Shuffled,
+            rebuild_interval: None,
+            measure_throughput: true,
+            measure_latency: true,
+            measure_construction: true,
+            latency_histograms: false,
+            perf_counters: false,
+            raw_samples: false,
+            seed: DEFAULT_SEED,
+            densities: Vec::new(),
+            min_log_size: None,
+            max_log_size: None,
+            log_size_step: None,
+            min_running_time: MINIMUM_RUNNING_TIME / NANOS_PER_MILLI,
+            chunk_time: CHUNK_TIME / NANOS_PER_MILLI,
+            rounds: NUM_ROUNDS,
+            memory_budget: None,
+            runner_timeout: DEFAULT_RUNNER_TIMEOUT,
+            formats: vec![Format::Csv],
+            output_policy: OutputPolicy::Overwrite,
+            run_id: generate_run_id(),
+        }
+    }
+}
+
+impl Settings {
+    /// Check the constraints between settings that clap cannot check for a single argument.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        match (self.min_log_size, self.max_log_size) {
+            (Some(min), Some(max)) if min > max => Err(format!("the minimum log size {min} exceeds the maximum log size {max}")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the runner or library with the given name is selected by the library patterns.
+    pub(crate) fn selects_runner(&self, name: &str) -> bool {
+        self.libraries.is_empty() || self.libraries.iter().any(|pattern| pattern.matches(name))
+    }
+
+    /// The configured densities, or the `default` density of a suite if none are configured.
+    pub(crate) fn densities(&self, default: f64) -> Vec<f64> {
+        if self.densities.is_empty() {
+            vec![default]
+        } else {
+            self.densities.clone()
+        }
+    }
+
+    /// Restrict the default sizes of a suite to the configured size range, or replace them with
+    /// the configured powers of two if a step is given.
+    pub(crate) fn sizes(&self, defaults: &[usize]) -> Vec<usize> {
+        let min_log_size = self
+            .min_log_size
+            .unwrap_or_else(|| defaults.iter().min().map_or(0, |size| size.ilog2()));
+        let max_log_size = self
+            .max_log_size
+            .unwrap_or_else(|| defaults.iter().max().map_or(0, |size| size.ilog2()));
+
+        match self.log_size_step {
+            Some(step) => (min_log_size..=max_log_size)
+                .step_by(step.max(1) as usize)
+                .map(|log_size| 1 << log_size)
+                .collect(),
+            None => defaults
+                .iter()
+                .copied()
+                .filter(|size| (min_log_size..=max_log_size).contains(&size.ilog2()))
+                .collect(),
+        }
+    }
+
+    /// The memory budget in bytes. Without a configured budget or known physical memory, the budget
+    /// is unlimited.
+    pub(crate) fn memory_budget_bytes(&self) -> usize {
+        match self.memory_budget {
+            Some(mib) => mib.saturating_mul(BYTES_PER_MIB),
+            None => total_memory().map_or(usize::MAX, |total| (total as f64 * DEFAULT_MEMORY_FRACTION) as usize),
+        }
+    }
+
+    /// The wall-clock time a runner may spend at a single size.
+    pub(crate) fn runner_timeout(&self) -> Duration {
+        Duration::from_secs(self.runner_timeout)
+    }
+
+    /// The minimum running time of a sample in nanoseconds.
+    pub(crate) fn minimum_running_time_nanos(&self) -> u64 {
+        self.min_running_time * NANOS_PER_MILLI
+    }
+
+    /// The time each runner is sampled per round in nanoseconds.
+    pub(crate) fn chunk_time_nanos(&self) -> u64 {
+        self.chunk_time * NANOS_PER_MILLI
+    }
+}
+
+/// Parse a density of one bits, which must lie strictly between zero and one.
+fn parse_density(value: &str) -> Result<f64, String> {
+    let density = value.parse::<f64>().map_err(|e| e.to_string())?;
+    if density > 0.0 && density < 1.0 {
+        Ok(density)
+    } else {
+        Err(format!("the density must lie strictly between 0 and 1, got {}", density))
+    }
+}
@@ -0,0 +1,450 @@
+//! Result sinks write the rows of a benchmark in a stable schema. Every row has the same named
+//! columns in the same order, and new columns are only ever appended, so downstream tooling can
+//! select columns by name and keeps working when the schema grows. Units are part of the column
+//! names, and every row references the run it belongs to and the metadata sidecar describing it.
+
+use crate::measure::MeasurementKind;
+use crate::metadata;
+use crate::perf::EVENTS;
+use crate::settings::Settings;
+use crate::statistics::Statistics;
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+use std::fmt::{Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The version of the schema, increased whenever existing columns change their meaning.
+/// Appending columns does not change the version.
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+
+/// The columns of every result row, in order. Times are nanoseconds per query, or per construction
+/// for rows of the `build` kind, and hardware events are counted per query.
+pub(crate) const COLUMNS: [&str; 31] = [
+    "benchmark",
+    "runner",
+    "size",
+    "kind",
+    "status",
+    "seed",
+    "samples",
+    "mean_ns",
+    "median_ns",
+    "std_dev_ns",
+    "mad_ns",
+    "min_ns",
+    "max_ns",
+    "p1_ns",
+    "p5_ns",
+    "p95_ns",
+    "p99_ns",
+    "ci_lower_ns",
+    "ci_upper_ns",
+    "mild_outliers",
+    "severe_outliers",
+    "instructions_per_query",
+    "cycles_per_query",
+    "branch_misses_per_query",
+    "cache_misses_per_query",
+    "l1d_misses_per_query",
+    "dtlb_misses_per_query",
+    "schema_version",
+    "metadata",
+    "run_id",
+    "density",
+];
+
+/// The columns of the raw sample files. Every row holds all samples of a runner at one size, separated
+/// by spaces, each the total duration in nanoseconds of `repetitions` calls.
+pub(crate) const SAMPLE_COLUMNS: [&str; 11] = ["benchmark", "runner", "size", "kind", "seed", "repetitions", "samples_ns", "metadata", "run_id", "density", "schema_version"];
+
+/// The columns of the latency histogram files. Each row is one bucket of the histogram of a runner.
+pub(crate) const HISTOGRAM_COLUMNS: [&str; 12] = ["benchmark", "runner", "size", "kind", "low_ns", "high_ns", "count", "run_id", "density", "seed", "schema_version", "metadata"];
+
+/// The columns of the memory files. Each row is the heap size of one library, as built for rank or
+/// select support.
+pub(crate) const MEMORY_COLUMNS: [&str; 10] = ["benchmark", "runner", "support", "size", "heap_bytes", "overhead_percent", "seed", "run_id", "schema_version", "metadata"];
+
+/// The file formats results can be written in.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// Comma-separated values with a header row naming the columns.
+    Csv,
+
+    /// One JSON object per line, keyed by the column names.
+    Jsonl,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// How the output files of a run treat the files of earlier runs.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum OutputPolicy {
+    /// Replace the files of earlier runs.
+    Overwrite,
+
+    /// Append the rows to the files of earlier runs. The rows of each run are distinguished by the
+    /// run id column.
+    Append,
+
+    /// Write a separate set of files for every run, named after the run id.
+    Timestamped,
+}
+
+/// Generate the id of a new run from the current UTC time, e.g. `20240131T235959Z`.
+pub(crate) fn generate_run_id() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+
+    // convert the days since the epoch into a proleptic Gregorian date (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!("{:04}{:02}{:02}T{:02}{:02}{:02}Z", year, month, day, seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// The path of the output file `<stem>.<extension>` of the current run. Under the timestamped
+/// policy, the run id is appended to the stem.
+pub(crate) fn output_path(output_dir: &Path, stem: &str, extension: &str, settings: &Settings) -> PathBuf {
+    match settings.output_policy {
+        OutputPolicy::Overwrite | OutputPolicy::Append => output_dir.join(format!("{}.{}", stem, extension)),
+        OutputPolicy::Timestamped => output_dir.join(format!("{}_{}.{}", stem, settings.run_id, extension)),
+    }
+}
+
+/// Open the output file `<stem>.<extension>` of the current run according to the output policy, and
+/// write the `header` line if the file is empty. When appending to a file with a different header, the
+/// rows would not match the columns, so this fails instead.
+pub(crate) fn open_output(output_dir: &Path, stem: &str, extension: &str, header: Option<&str>, settings: &Settings) -> io::Result<File> {
+    let path = output_path(output_dir, stem, extension, settings);
+    let append = settings.output_policy == OutputPolicy::Append;
+    let mut file = OpenOptions::new().create(true).write(true).truncate(!append).append(append).read(true).open(&path)?;
+
+    if let Some(header) = header {
+        let mut existing = String::new();
+        BufReader::new(&file).read_line(&mut existing)?;
+        if existing.is_empty() {
+            writeln!(file, "{}", header)?;
+        } else if existing.trim_end() != header {
+            return Err(io::Error::other(format!("{} has different columns than this version of the harness writes, use another output policy or file", path.display())));
+        }
+    }
+    Ok(file)
+}
+
+/// The outcome of measuring a runner at one size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Status {
+    /// The runner was measured.
+    Ok,
+
+    /// The runner was skipped because it would exceed the memory budget.
+    Skipped,
+
+    /// The runner was skipped because the size exceeds its size limit.
+    Unsupported,
+
+    /// The runner exceeded the timeout and is dropped from all remaining sizes.
+    Timeout,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Ok => write!(f, "ok"),
+            Status::Skipped => write!(f, "skipped"),
+            Status::Unsupported => write!(f, "unsupported"),
+            Status::Timeout => write!(f, "timeout"),
+        }
+    }
+}
+
+/// The run a row belongs to, referenced by the `run_id` and `metadata` columns of every row.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Run<'r> {
+    pub(crate) id: &'r str,
+    pub(crate) metadata: &'r str,
+}
+
+/// A row of one kind of output file. All rows of a file have the same columns.
+pub(crate) trait Row {
+    /// The columns of the row, in order.
+    const COLUMNS: &'static [&'static str];
+
+    /// The values of the row in the order of the [`COLUMNS`](Row::COLUMNS). Missing values are `null`.
+    fn values(&self, run: Run) -> Vec<Value>;
+}
+
+/// The result of one runner at one size. Runners that were not measured have no statistics, and
+/// events that were not counted have no value; both are written as empty fields.
+pub(crate) struct ResultRow<'r> {
+    pub(crate) benchmark: &'r str,
+    pub(crate) runner: &'r str,
+    pub(crate) size: usize,
+    /// The density of the input, for runners configured with one.
+    pub(crate) density: Option<f64>,
+    pub(crate) kind: MeasurementKind,
+    pub(crate) status: Status,
+    pub(crate) seed: u64,
+    pub(crate) stats: Option<&'r Statistics>,
+    pub(crate) counters: [Option<f64>; EVENTS],
+}
+
+impl Row for ResultRow<'_> {
+    const COLUMNS: &'static [&'static str] = &COLUMNS;
+
+    fn values(&self, run: Run) -> Vec<Value> {
+        let mut values = vec![
+            json!(self.benchmark),
+            json!(self.runner),
+            json!(self.size),
+            json!(self.kind.to_string()),
+            json!(self.status.to_string()),
+            json!(self.seed),
+        ];
+
+        match self.stats {
+            Some(s) => values.extend([
+                json!(s.samples),
+                json!(s.mean),
+                json!(s.median),
+                json!(s.std_dev),
+                json!(s.mad),
+                json!(s.min),
+                json!(s.max),
+                json!(s.p1),
+                json!(s.p5),
+                json!(s.p95),
+                json!(s.p99),
+                json!(s.ci_lower),
+                json!(s.ci_upper),
+                json!(s.mild_outliers),
+                json!(s.severe_outliers),
+            ]),
+            None => values.resize(values.len() + 15, Value::Null),
+        }
+
+        values.extend(self.counters.map(|value| json!(value)));
+        values.push(json!(SCHEMA_VERSION));
+        values.push(json!(run.metadata));
+        values.push(json!(run.id));
+        values.push(json!(self.density));
+        values
+    }
+}
+
+/// The raw samples of one runner at one size.
+pub(crate) struct SampleRow<'r> {
+    pub(crate) benchmark: &'r str,
+    pub(crate) runner: &'r str,
+    pub(crate) size: usize,
+    pub(crate) density: Option<f64>,
+    pub(crate) kind: MeasurementKind,
+    pub(crate) seed: u64,
+    /// The number of calls timed by every sample.
+    pub(crate) repetitions: u64,
+    pub(crate) samples: &'r [u64],
+}
+
+impl Row for SampleRow<'_> {
+    const COLUMNS: &'static [&'static str] = &SAMPLE_COLUMNS;
+
+    fn values(&self, run: Run) -> Vec<Value> {
+        let samples = self.samples.iter().map(u64::to_string).collect::<Vec<_>>().join(" ");
+        vec![
+            json!(self.benchmark),
+            json!(self.runner),
+            json!(self.size),
+            json!(self.kind.to_string()),
+            json!(self.seed),
+            json!(self.repetitions),
+            json!(samples),
+            json!(run.metadata),
+            json!(run.id),
+            json!(self.density),
+            json!(SCHEMA_VERSION),
+        ]
+    }
+}
+
+/// One bucket of the latency histogram of a runner at one size.
+pub(crate) struct HistogramRow<'r> {
+    pub(crate) benchmark: &'r str,
+    pub(crate) runner: &'r str,
+    pub(crate) size: usize,
+    pub(crate) density: Option<f64>,
+    pub(crate) kind: MeasurementKind,
+    pub(crate) seed: u64,
+    /// The inclusive lower and exclusive upper bound of the bucket in nanoseconds.
+    pub(crate) bounds_ns: (f64, f64),
+    pub(crate) count: u64,
+}
+
+impl Row for HistogramRow<'_> {
+    const COLUMNS: &'static [&'static str] = &HISTOGRAM_COLUMNS;
+
+    fn values(&self, run: Run) -> Vec<Value> {
+        vec![
+            json!(self.benchmark),
+            json!(self.runner),
+            json!(self.size),
+            json!(self.kind.to_string()),
+            json!(self.bounds_ns.0),
+            json!(self.bounds_ns.1),
+            json!(self.count),
+            json!(run.id),
+            json!(self.density),
+            json!(self.seed),
+            json!(SCHEMA_VERSION),
+            json!(run.metadata),
+        ]
+    }
+}
+
+/// The heap size of one library at one size.
+pub(crate) struct MemoryRow<'r> {
+    pub(crate) benchmark: &'r str,
+    pub(crate) runner: &'r str,
+    /// Whether the library was built for rank or for select queries.
+    pub(crate) support: &'r str,
+    pub(crate) size: usize,
+    pub(crate) heap_bytes: usize,
+    /// The heap size relative to the raw bit vector, minus 100%.
+    pub(crate) overhead_percent: f64,
+    pub(crate) seed: u64,
+}
+
+impl Row for MemoryRow<'_> {
+    const COLUMNS: &'static [&'static str] = &MEMORY_COLUMNS;
+
+    fn values(&self, run: Run) -> Vec<Value> {
+        vec![
+            json!(self.benchmark),
+            json!(self.runner),
+            json!(self.support),
+            json!(self.size),
+            json!(self.heap_bytes),
+            json!(self.overhead_percent),
+            json!(self.seed),
+            json!(run.id),
+            json!(SCHEMA_VERSION),
+            json!(run.metadata),
+        ]
+    }
+}
+
+/// A destination for the rows of one output file.
+pub(crate) trait ResultSink {
+    /// Write the values of one row, in the order of the columns the sink was created with.
+    fn write(&mut self, values: &[Value]) -> io::Result<()>;
+}
+
+/// Writes rows as comma-separated values, preceded by a header row naming the columns.
+pub(crate) struct CsvSink {
+    writer: BufWriter<File>,
+}
+
+impl CsvSink {
+    pub(crate) fn create(output_dir: &Path, name: &str, columns: &[&str], settings: &Settings) -> io::Result<Self> {
+        let file = open_output(output_dir, name, Format::Csv.extension(), Some(&columns.join(",")), settings)?;
+        Ok(Self { writer: BufWriter::new(file) })
+    }
+
+    /// Create a sink writing to the given path, replacing an existing file.
+    pub(crate) fn create_file(path: &Path, columns: &[&str]) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", columns.join(","))?;
+        Ok(Self { writer })
+    }
+}
+
+impl ResultSink for CsvSink {
+    fn write(&mut self, values: &[Value]) -> io::Result<()> {
+        let fields = values.iter().map(csv_field).collect::<Vec<_>>();
+        writeln!(self.writer, "{}", fields.join(","))?;
+        self.writer.flush()
+    }
+}
+
+/// Render a value as a CSV field, quoting strings containing separators.
+fn csv_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) if s.contains([',', '"', '\n']) => format!("\"{}\"", s.replace('"', "\"\"")),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Writes every row as one JSON object per line, keyed by the column names.
+pub(crate) struct JsonLinesSink {
+    writer: BufWriter<File>,
+    columns: &'static [&'static str],
+}
+
+impl JsonLinesSink {
+    pub(crate) fn create(output_dir: &Path, name: &str, columns: &'static [&'static str], settings: &Settings) -> io::Result<Self> {
+        let file = open_output(output_dir, name, Format::Jsonl.extension(), None, settings)?;
+        Ok(Self { writer: BufWriter::new(file), columns })
+    }
+}
+
+impl ResultSink for JsonLinesSink {
+    fn write(&mut self, values: &[Value]) -> io::Result<()> {
+        let object = self.columns.iter().map(|column| column.to_string()).zip(values.iter().cloned()).collect::<Map<_, _>>();
+        writeln!(self.writer, "{}", Value::Object(object))?;
+        self.writer.flush()
+    }
+}
+
+/// The sinks of one output file of a run, one per configured [`Format`]. All rows written to them
+/// have the columns the sinks were created for.
+pub(crate) struct Sinks {
+    sinks: Vec<Box<dyn ResultSink>>,
+    columns: &'static [&'static str],
+    run_id: String,
+    metadata: String,
+}
+
+impl Sinks {
+    /// Create a sink for every configured format, writing rows of type `R` to `<name>.<extension>` in
+    /// the output directory according to the output policy.
+    pub(crate) fn create<R: Row>(output_dir: &Path, name: &str, settings: &Settings) -> io::Result<Self> {
+        let mut sinks: Vec<Box<dyn ResultSink>> = Vec::with_capacity(settings.formats.len());
+        for &format in &settings.formats {
+            match format {
+                Format::Csv => sinks.push(Box::new(CsvSink::create(output_dir, name, R::COLUMNS, settings)?)),
+                Format::Jsonl => sinks.push(Box::new(JsonLinesSink::create(output_dir, name, R::COLUMNS, settings)?)),
+            }
+        }
+        Ok(Self { sinks, columns: R::COLUMNS, run_id: settings.run_id.clone(), metadata: metadata::file_name(settings) })
+    }
+
+    /// Write a row of the current run to every sink. Failures are reported, but do not abort the
+    /// benchmark.
+    pub(crate) fn write<R: Row>(&mut self, row: &R) {
+        debug_assert_eq!(R::COLUMNS, self.columns, "the row does not match the columns of the sinks");
+        let values = row.values(Run { id: &self.run_id, metadata: &self.metadata });
+        debug_assert_eq!(values.len(), self.columns.len());
+        for sink in &mut self.sinks {
+            sink.write(&values).unwrap_or_else(|e| eprintln!("WARNING: {}", e));
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// How many resamples are drawn to estimate the confidence interval of the mean.
+pub(crate) const BOOTSTRAP_RESAMPLES: usize = 1000;
+
+/// The confidence level of the bootstrap confidence interval.
+pub(crate) const CONFIDENCE_LEVEL: f64 = 0.95;
+
+/// Seed of the bootstrap resampling, so the same samples always yield the same interval.
+const BOOTSTRAP_SEED: u64 = 0xB007_57A9;
+
+/// Summary statistics of the samples of one measurement, normalized to nanoseconds per call.
+#[derive(Clone, Debug)]
+pub(crate) struct Statistics {
+    /// The number of samples.
+    pub(crate) samples: usize,
+
+    pub(crate) mean: f64,
+
+    pub(crate) median: f64,
+
+    /// The sample standard deviation around the mean.
+    pub(crate) std_dev: f64,
+
+    /// The median absolute deviation from the median.
+    pub(crate) mad: f64,
+
+    pub(crate) min: f64,
+
+    pub(crate) max: f64,
+
+    pub(crate) p1: f64,
+
+    pub(crate) p5: f64,
+
+    pub(crate) p95: f64,
+
+    pub(crate) p99: f64,
+
+    /// Lower bound of the bootstrap confidence interval of the mean.
+    pub(crate) ci_lower: f64,
+
+    /// Upper bound of the bootstrap confidence interval of the mean.
+    pub(crate) ci_upper: f64,
+
+    /// Samples outside the inner Tukey fences (1.5 times the interquartile range beyond the quartiles),
+    /// but inside the outer fences.
+    pub(crate) mild_outliers: usize,
+
+    /// Samples outside the outer Tukey fences (3 times the interquartile range beyond the quartiles).
+    pub(crate) severe_outliers: usize,
+}
+
+impl Statistics {
+    /// Compute the statistics of a non-empty list of samples.
+    pub(crate) fn from_samples(samples: &[f64]) -> Self {
+        assert!(!samples.is_empty(), "cannot compute statistics without samples");
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable_by(f64::total_cmp);
+
+        let mean = mean(&sorted);
+        let median = percentile(&sorted, 0.5);
+        let std_dev = if sorted.len() > 1 {
+            (sorted.iter().map(|&x| (x - mean).powi(2)).sum::<f64>() / (sorted.len() - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+
+        let mut deviations = sorted.iter().map(|&x| (x - median).abs()).collect::<Vec<_>>();
+        deviations.sort_unstable_by(f64::total_cmp);
+        let mad = percentile(&deviations, 0.5);
+
+        let (ci_lower, ci_upper) = bootstrap_mean_interval(&sorted);
+
+        let q1 = percentile(&sorted, 0.25);
+        let q3 = percentile(&sorted, 0.75);
+        let iqr = q3 - q1;
+        let severe = |x: f64| x < q1 - 3.0 * iqr || x > q3 + 3.0 * iqr;
+        let mild = |x: f64| !severe(x) && (x < q1 - 1.5 * iqr || x > q3 + 1.5 * iqr);
+
+        Self {
+            samples: sorted.len(),
+            mean,
+            median,
+            std_dev,
+            mad,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            p1: percentile(&sorted, 0.01),
+            p5: percentile(&sorted, 0.05),
+            p95: percentile(&sorted, 0.95),
+            p99: percentile(&sorted, 0.99),
+            ci_lower,
+            ci_upper,
+            mild_outliers: sorted.iter().filter(|&&x| mild(x)).count(),
+            severe_outliers: sorted.iter().filter(|&&x| severe(x)).count(),
+        }
+    }
+
+    /// Compute the statistics of raw samples, each the total duration of `repetitions` calls, normalized
+    /// to the duration per call.
+    pub(crate) fn from_raw_samples(samples: &[u64], repetitions: u64) -> Self {
+        let per_call = samples.iter().map(|&sample| sample as f64 / repetitions as f64).collect::<Vec<_>>();
+        Self::from_samples(&per_call)
+    }
+
+    /// The standard deviation relative to the mean.
+    pub(crate) fn relative_std_dev(&self) -> f64 {
+        self.std_dev / self.mean
+    }
+
+    /// The two-sided p-value of Welch's t-test, which tests whether the means of two measurements
+    /// differ without assuming equal variances. `None` if either measurement has fewer than two samples.
+    pub(crate) fn welch_p_value(&self, other: &Statistics) -> Option<f64> {
+        if self.samples < 2 || other.samples < 2 {
+            return None;
+        }
+
+        let variance_a = self.std_dev.powi(2) / self.samples as f64;
+        let variance_b = other.std_dev.powi(2) / other.samples as f64;
+        let variance = variance_a + variance_b;
+        if variance == 0.0 {
+            return Some(if self.mean == other.mean { 1.0 } else { 0.0 });
+        }
+
+        let t = (self.mean - other.mean) / variance.sqrt();
+        // Welch-Satterthwaite approximation of the degrees of freedom
+        let df = variance.powi(2) / (variance_a.powi(2) / (self.samples - 1) as f64 + variance_b.powi(2) / (other.samples - 1) as f64);
+        Some(regularized_incomplete_beta(df / (df + t * t), df / 2.0, 0.5))
+    }
+}
+
+fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+/// The `fraction` percentile of sorted samples, linearly interpolated between the closest ranks.
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    let rank = fraction * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Estimate the confidence interval of the mean with the percentile bootstrap.
+fn bootstrap_mean_interval(samples: &[f64]) -> (f64, f64) {
+    let mut rng = StdRng::seed_from_u64(BOOTSTRAP_SEED);
+    let mut means = (0..BOOTSTRAP_RESAMPLES)
+        .map(|_| (0..samples.len()).map(|_| samples[rng.gen_range(0..samples.len())]).sum::<f64>() / samples.len() as f64)
+        .collect::<Vec<_>>();
+    means.sort_unstable_by(f64::total_cmp);
+
+    let tail = (1.0 - CONFIDENCE_LEVEL) / 2.0;
+    (percentile(&means, tail), percentile(&means, 1.0 - tail))
+}
+
+/// The natural logarithm of the gamma function for positive arguments (Lanczos approximation).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [76.180_091_729_471_46, -86.505_320_329_416_77, 24.014_098_240_830_91, -1.231_739_572_450_155, 0.001_208_650_973_866_179, -0.000_005_395_239_384_953];
+
+    let tmp = x + 5.5;
+    let series = COEFFICIENTS.iter().enumerate().fold(1.000_000_000_190_015, |sum, (i, c)| sum + c / (x + 1.0 + i as f64));
+    (2.506_628_274_631_000_5 * series / x).ln() - tmp + (x + 0.5) * tmp.ln()
+}
+
+/// The regularized incomplete beta function I_x(a, b), evaluated with its continued fraction.
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // the continued fraction converges quickly only below this point, use the symmetry otherwise
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+/// The continued fraction of the incomplete beta function, evaluated with the modified Lentz method.
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 300;
+    const EPSILON: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+
+    let clamp = |value: f64| if value.abs() < TINY { TINY } else { value };
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut fraction = d;
+
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / clamp(1.0 + even * d);
+        c = clamp(1.0 + even / c);
+        fraction *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / clamp(1.0 + odd * d);
+        c = clamp(1.0 + odd / c);
+        let delta = d * c;
+        fraction *= delta;
+
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Statistics with the given number of samples, mean and standard deviation, as far as the
+    /// t-test reads them.
+    fn summary(samples: usize, mean: f64, std_dev: f64) -> Statistics {
+        Statistics { samples, mean, std_dev, ..Statistics::from_samples(&[mean]) }
+    }
+
+    #[test]
+    fn percentile_interpolates_between_ranks() {
+        let sorted = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(percentile(&sorted, 0.0), 1.0);
+        assert_eq!(percentile(&sorted, 0.25), 2.0);
+        assert_eq!(percentile(&sorted, 0.5), 3.0);
+        assert!((percentile(&sorted, 0.1) - 1.4).abs() < 1e-12);
+        assert_eq!(percentile(&sorted, 1.0), 5.0);
+    }
+
+    #[test]
+    fn summary_of_known_samples() {
+        let statistics = Statistics::from_samples(&[4.0, 1.0, 3.0, 2.0, 5.0]);
+        assert_eq!(statistics.mean, 3.0);
+        assert_eq!(statistics.median, 3.0);
+        assert_eq!(statistics.mad, 1.0);
+        assert!((statistics.std_dev - 2.5f64.sqrt()).abs() < 1e-12);
+        assert_eq!((statistics.min, statistics.max), (1.0, 5.0));
+    }
+
+    #[test]
+    fn incomplete_beta_closed_forms() {
+        // I_x(1, 1) = x, and I_0.5(a, a) = 0.5 by symmetry
+        for x in [0.1, 0.3, 0.7, 0.9] {
+            assert!((regularized_incomplete_beta(x, 1.0, 1.0) - x).abs() < 1e-10);
+        }
+        for a in [0.5, 2.0, 7.5] {
+            assert!((regularized_incomplete_beta(0.5, a, a) - 0.5).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn identical_measurements_do_not_differ() {
+        let statistics = Statistics::from_samples(&[10.0, 12.0, 11.0, 13.0, 9.0]);
+        let p_value = statistics.welch_p_value(&statistics.clone()).unwrap();
+        assert!((p_value - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn welch_p_value_matches_t_table() {
+        // two samples of 6 with unit standard deviation have 10 degrees of freedom, and the critical
+        // value of the two-sided t-test at the 5% level is 2.228
+        let a = summary(6, 0.0, 1.0);
+        let t = 2.228_139;
+        let b = summary(6, t * (2.0f64 / 6.0).sqrt(), 1.0);
+        assert!((a.welch_p_value(&b).unwrap() - 0.05).abs() < 1e-5);
+
+        // two samples of 2 have 2 degrees of freedom, where the two-sided p-value is 1 - t / sqrt(t^2 + 2)
+        let a = summary(2, 0.0, 1.0);
+        let b = summary(2, 1.0, 1.0);
+        assert!((a.welch_p_value(&b).unwrap() - (1.0 - 1.0 / 3.0f64.sqrt())).abs() < 1e-8);
+    }
+
+    #[test]
+    fn too_few_samples_have_no_p_value() {
+        assert!(summary(1, 0.0, 0.0).welch_p_value(&summary(5, 1.0, 1.0)).is_none());
+    }
+}